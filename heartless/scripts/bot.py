@@ -0,0 +1,60 @@
+#!/usr/bin/env python3
+
+# An example of AI script run by "--script-mode json-lines", e.g.
+# "heartless --others scripts/bot.py --script-mode json-lines".
+#
+# It is started once per game, reads the events and requests in JSON Lines from stdin,
+# and answers each request with one line of JSON telling the cards to pass or discard.
+# Unlike "replica.py", it follows the trick in progress from the events, and
+# picks from the cards the "legal" field of each request tells it may discard.
+
+import json
+import sys
+
+ranks = '234567890JQKA'
+
+def rank_of(card):
+    return ranks.index(card[0])
+
+def suit_of(card):
+    return card[1]
+
+def points_of(card):
+    if suit_of(card) == 'h':
+        return 1
+    if card == 'Qs':
+        return 13
+    return 0
+
+trick = [] # the cards played in the current trick
+
+def pick_three_cards(hand):
+    return sorted(hand, key=lambda card: (points_of(card) > 0, rank_of(card)))[-3:]
+
+def pick_card(legal):
+    if len(trick) == 0: # lead the lowest card
+        return min(legal, key=rank_of)
+    suit = suit_of(trick[0])
+    if suit_of(legal[0]) == suit: # play the highest card under the winning one, or the lowest
+        high_card = max([card for card in trick if suit_of(card) == suit], key=rank_of)
+        lower = [card for card in legal if rank_of(card) < rank_of(high_card)]
+        return max(lower, key=rank_of) if len(lower) > 0 else min(legal, key=rank_of)
+    return max(legal, key=lambda card: (points_of(card), rank_of(card)))
+
+def answer(cards):
+    print(json.dumps({'cards': ' '.join(cards)}), flush=True)
+
+for line in sys.stdin:
+    message = json.loads(line)
+    event = message['event']
+    if event == 'trick_started':
+        trick.clear()
+    elif event == 'card_played':
+        trick.append(message['card'])
+    elif event == 'undone': # the last move was taken back, follow the trick in progress again
+        trick[:] = [play['card'] for play in message['trick']]
+    elif event == 'request_pass':
+        answer(pick_three_cards(message['hand'].split()))
+    elif event == 'request_play': # resync the trick, in case the script started in the middle of the hand
+        trick[:] = [play['card'] for play in message['trick']]
+        answer([pick_card(message['legal'].split())])
@@ -7,12 +7,25 @@ import sys
 long_opts = [
     'who=',
     'hand=',
+    'legal=',
     'discarded=',
     'suit_to_follow=',
     'round=',
     'under_the_gun=',
     'round_winner=',
     'high_card=',
+    'jack_of_diamonds=',
+    'partner=',
+    'rule_pack=',
+    'pass_to=',
+    'passed=',
+    'received=',
+    'tricks=',
+    'seats=',
+    'discardings=',
+    'hand_scores=',
+    'game_scores=',
+    'suit_chars=',
     'my_discarding=',
     'my_hand_score=',
     'my_game_score=',
@@ -98,6 +111,8 @@ for opt,val in opts:
         who = int(val)
     elif opt == '--hand':
         hand = extract_cards(val)
+    elif opt == '--legal': # the cards to pass or discard legally
+        legal = extract_cards(val)
     elif opt == '--discarded':
         discarded = extract_cards(val)
     elif opt == '--suit_to_follow':
@@ -110,6 +125,23 @@ for opt,val in opts:
         round_winner = int(val)
     elif opt == '--high_card':
         high_card = extract_cards(val)
+    elif opt == '--jack_of_diamonds':
+        jack_of_diamonds = int(val)
+    elif opt == '--partner':
+        partner = int(val)
+    elif opt == '--rule_pack':
+        rule_pack = val
+    elif opt == '--pass_to':
+        pass_to = [int(receiver) for receiver in val.split(',') if receiver]
+    elif opt == '--passed':
+        passed = extract_cards(val)
+    elif opt == '--received':
+        received = extract_cards(val)
+    elif opt == '--tricks': # leader:cards in the order of discarding:winner, separated by commas
+        tricks = []
+        for trick in filter(None, val.split(',')):
+            leader, cards, winner = trick.split(':')
+            tricks.append((int(leader), [extract_cards(cards[i:i+2])[0] for i in range(0,len(cards),2)], int(winner)))
     elif opt == '--my_discarding':
         discardings[0] = extract_cards(val)
     elif opt == '--my_hand_score':
@@ -177,17 +209,12 @@ def output_cards(cards):
 def count_suit (hand,suit):
     return list(map(lambda rank_suit: rank_suit[1], hand)).count(suit)
 
-def value_of_passing_card(card):
-    if card == (12,'spade'):
-        return -14*4-4 # Queen of Spades is the first card to pass
-    else:
-        rank, suit = card
-        return -rank*4-suit_names.index(suit)
-
 def value_of_discarding_card(card):
     rank, suit = card
-    if round_ == 1 and rank == 12 and suit == 'spade':
-        return 0 # don't discard Queen of Spades in round 1.
+    if (round_ == 1 or partner_wins) and suit == 'spade' and points_of([card]) > 0:
+        return -rank/100 # don't discard penalty spades in round 1 or to the partner, unless only them left.
+    if jack_of_diamonds != 0 and rank == 11 and suit == 'diamond':
+        return -15 # keep the Jack of Diamonds unless only hearts left.
     if suit == 'heart':
         return -rank
     elif suit == 'club':
@@ -214,14 +241,112 @@ def contains_suit(hand,suit):
 def contains(hand,card):
     return any(map(lambda card_in_hand: card_in_hand == card, hand))
 
+def points_of(cards):
+    if rule_pack == 'spot-hearts':
+        points = sum(map(lambda card: card[0], cards_of_suit(cards,'heart')))
+    else:
+        points = count_suit(cards,'heart')
+    if contains(cards,(12,'spade')):
+        points += 13
+    if rule_pack == 'black-maria':
+        if contains(cards,(14,'spade')):
+            points += 7
+        if contains(cards,(13,'spade')):
+            points += 10
+    if rule_pack == 'pink-lady' and contains(cards,(12,'heart')):
+        points += 12
+    if contains(cards,(11,'diamond')):
+        points += jack_of_diamonds
+    return points
+
+def rank_of(card):
+    return card[0]
+
+def bit_of(card): # the order of cards in the heartless representation
+    return suit_names.index(card[1])*16 + card[0]
+
+deck = [(rank,suit) for suit in suit_names for rank in range(2,15)]
+unseen = [card for card in deck if not contains(discarded,card) and not contains(hand,card)]
+moon_points = points_of([card for card in deck if points_of([card]) > 0])
+
+def may_shoot_the_moon(hand,unseen):
+    high_cards = len(list(filter(lambda card: card[0] >= 11, hand)))
+    highest_heart = lambda cards: max([0] + [rank for rank, suit in cards if suit == 'heart'])
+    return high_cards*2 > len(hand) and highest_heart(unseen) <= highest_heart(hand)
+
+def moon_threat():
+    takers = [i for i in range(4) if hand_scores[i] != 0]
+    if len(takers) == 1 and takers[0] != who and takers[0] != partner and hand_scores[takers[0]]*2 > moon_points:
+        return takers[0]
+    return None
+
+def shooting_the_moon():
+    return all(map(lambda i: i == who or hand_scores[i] == 0, range(4))) and may_shoot_the_moon(hand,unseen)
+
+def pick_card_for_moon():
+    if under_the_gun == who:
+        sure = lambda card: not any(map(lambda other: other[1] == card[1] and other[0] > card[0], unseen))
+        return max(legal, key=lambda card: (sure(card), rank_of(card), bit_of(card)))
+    if contains_suit(hand,suit_to_follow):
+        high_rank = high_card[0][0] if len(high_card) > 0 else 0
+        cards = sorted(cards_of_suit(hand,suit_to_follow), key=rank_of)
+        return cards[-1] if cards[-1][0] > high_rank else cards[0]
+    cards = [card for card in legal if not (points_of([card]) > 0)]
+    return min(cards, key=lambda card: (rank_of(card), bit_of(card))) if len(cards) > 0 else None
+
+def pick_three_cards_to(to_left):
+    kept = [(11,'diamond')] if jack_of_diamonds != 0 else []
+    remaining = list(hand)
+    passing = []
+    def pass_cards(cards): # the highest first
+        for card in sorted(cards, key=bit_of, reverse=True):
+            if len(passing) < 3 and contains(remaining,card) and not contains(kept,card):
+                remaining.remove(card)
+                passing.append(card)
+    guards = [card for card in cards_of_suit(hand,'spade') if rank_of(card) < 12]
+    enough = 3 if to_left else 4 # the left may drop the Queen of Spades on A/K
+    keep_queen = contains(hand,(12,'spade')) and len(guards) >= enough
+    if not keep_queen:
+        pass_cards([(12,'spade')])
+        if len(guards) < 3:
+            pass_cards([(14,'spade'),(13,'spade')])
+    while True: # void clubs or diamonds, the shortest first
+        voids = [cards_of_suit(remaining,suit) for suit in ['club','diamond']]
+        voids = [cards for cards in voids if 0 < len(cards) <= 3-len(passing) and not any(map(lambda card: contains(kept,card), cards))]
+        if len(voids) == 0:
+            break
+        pass_cards(min(voids, key=len))
+    for spare in [guards if keep_queen else [], []]:
+        for rank in range(14,1,-1):
+            for suit in ['heart','spade','diamond','club']:
+                if not contains(spare,(rank,suit)):
+                    pass_cards([(rank,suit)])
+    return passing
+
+partner_wins = round_winner == partner
+
 if round_ == 0: # passing three cards
-    hand.sort(key=lambda card: value_of_passing_card(card))
-    output_cards(hand[0:3])
+    if may_shoot_the_moon(hand,unseen): # pass the lowest cards to shoot the moon
+        hand.sort(key=lambda card: (rank_of(card), bit_of(card)) if not (jack_of_diamonds != 0 and card == (11,'diamond')) else (15,0))
+        output_cards(hand[0:3])
+    else:
+        output_cards(pick_three_cards_to(pass_to == [(who+1)%4]))
 else: # discard one card
+    if shooting_the_moon():
+        card = pick_card_for_moon()
+        if card != None:
+            output_card(card)
+            exit()
+    trick = [card for discarding in discardings for card in discarding]
+    stopping = moon_threat() != None and moon_threat() == round_winner and points_of(trick) > 0
     if under_the_gun == who: # early hand
         if round_ == 1:
             output_card((2,'club'))
             exit()
+        if jack_of_diamonds != 0 and contains(hand,(11,'diamond')): # lead the Jack of Diamonds if nobody can beat it
+            if all(map(lambda rank: contains(discarded,(rank,'diamond')) or contains(hand,(rank,'diamond')), [12,13,14])):
+                output_card((11,'diamond'))
+                exit()
         suits_cnts = [
             ( 'club'   , count_suit(hand, 'club'   )),
             ( 'diamond', count_suit(hand, 'diamond')),
@@ -243,34 +368,51 @@ else: # discard one card
         rank_to_discard = 14
         high_rank_to_discard = 0
 
+        late_hand_follows = False
+        for i in range(1,3):
+            j = (who+i)%4
+            if j == under_the_gun:
+                break
+            if suit_to_follow in may_have_suits[j]:
+                late_hand_follows = True
+
+        if jack_of_diamonds != 0 and suit_to_follow == 'diamond': # try to win the Jack of Diamonds
+            if contains(trick,(11,'diamond')) and points_of(trick) < 0 and cards[0][0] > high_rank:
+                output_card(cards[0])
+                exit()
+            if contains(hand,(11,'diamond')) and 11 > high_rank and not late_hand_follows and points_of(trick+[(11,'diamond')]) < 0:
+                output_card((11,'diamond'))
+                exit()
+
+        if stopping and cards[0][0] > high_rank: # take the points to stop the moon
+            output_card(cards[0])
+            exit()
+
         for card in cards:
             rank, _ = card
             if rank < high_rank:
-                if suit_to_follow == 'spade' and contains(hand,(12,'spade')) and 12 < high_rank:
+                if jack_of_diamonds != 0 and card == (11,'diamond') and any(map(lambda card: card[0] < 11, cards)):
+                    continue # keep the Jack of Diamonds, discarding a lower diamond
+                if partner_wins and card == (12,'spade') and any(map(lambda card: card[0] < 12, cards)):
+                    continue # keep the Queen of Spades away from the partner, discarding a lower spade
+                if suit_to_follow == 'spade' and contains(hand,(12,'spade')) and 12 < high_rank and not partner_wins:
                     output_card((12,'spade'))
                     exit()
                 output_card(card)
                 exit()
             if rank_to_discard >= rank:
                 rank_to_discard = rank
-                if high_rank_to_discard < rank and suit_to_follow != 'heart' and card != (12, 'spade'):
+                if high_rank_to_discard < rank and suit_to_follow != 'heart' and points_of([card]) <= 0:
                     high_rank_to_discard = rank
 
-        late_hand_follows = False
-        for i in range(1,3):
-            j = (who+i)%4
-            if j == under_the_gun:
-                break
-            if suit_to_follow in may_have_suits[j]:
-                late_hand_follows = True
-
         output_card((rank_to_discard if late_hand_follows or high_rank_to_discard == 0 else high_rank_to_discard, suit_to_follow))
         exit()
     else: # don't follow suit
-        if contains(hand,(12,'spade')) and round_ != 1: # try to discard Queen of Spades
+        partner_wins = partner_wins or moon_threat() == round_winner # no points to the partner or the moon shooter
+        if contains(hand,(12,'spade')) and round_ != 1 and not partner_wins: # try to discard Queen of Spades
             output_card((12,'spade'))
             exit()
-        if contains_suit(hand,'heart') and round_ != 1: # try to discard hearts
+        if contains_suit(hand,'heart') and round_ != 1 and not partner_wins: # try to discard hearts
             cards = cards_of_suit(hand,'heart')
             cards.sort(key=lambda rank_suit: -rank_suit[0])
             output_card(cards[0])
@@ -0,0 +1,85 @@
+//! Long-lived AI scripts, started once per game and talking JSON Lines over stdin/stdout.
+//!
+//! The script is started with the argument "--who=N" and receives the game events in the player's view,
+//! as "--json" prints them, see `json` module, with requests for its cards in between. It answers each request
+//! with one line of cards, e.g. "2c 3c 4c", or a JSON object with a "cards" field, e.g. {"cards":"2c 3c 4c"}.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use wait_timeout::ChildExt;
+
+/// How long the script may take to exit by itself after its stdin is closed, before it is killed.
+const EXIT_TIMEOUT: Duration = Duration::from_millis( 500 );
+
+/// How the AI scripts are run.
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum )]
+pub enum ScriptMode {
+    /// Runs the script for each pass and each card, with the game state in its arguments.
+    #[default]
+    PerCall,
+    /// Starts the script once per game, sending events and requests in JSON Lines to its stdin, see `Bot`.
+    JsonLines,
+}
+
+/// A script started once per game in `ScriptMode::JsonLines`.
+#[derive( Debug )]
+pub struct Bot {
+    child : Child,
+    stdin : Option<ChildStdin>,
+    lines : Receiver<String>,
+}
+
+impl Bot {
+    /// Starts the script for the player.
+    pub fn spawn( path: &Path, who: usize ) -> io::Result<Self> {
+        let mut child = Command::new( path )
+            .arg( format!( "--who={who}" ))
+            .stdin( Stdio::piped() )
+            .stdout( Stdio::piped() )
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().ok_or( io::ErrorKind::BrokenPipe )?;
+        let ( sender, lines ) = mpsc::channel();
+        thread::spawn( move || {
+            for line in BufReader::new( stdout ).lines().map_while( Result::ok ) {
+                if sender.send( line ).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok( Bot{ child, stdin, lines })
+    }
+    /// Sends one line of JSON to the script.
+    pub fn send( &mut self, line: &str ) -> io::Result<()> {
+        let stdin = self.stdin.as_mut().ok_or( io::ErrorKind::BrokenPipe )?;
+        writeln!( stdin, "{line}" )?;
+        stdin.flush()
+    }
+    /// Receives the next line from the script, waiting no longer than the timeout if given.
+    ///
+    /// Returns `None` if the script has exited or run out of time.
+    pub fn recv( &self, timeout: Option<Duration> ) -> Option<String> {
+        match timeout {
+            Some( timeout ) => self.lines.recv_timeout( timeout ).ok(),
+            None            => self.lines.recv().ok(),
+        }
+    }
+}
+
+impl Drop for Bot {
+    /// Closes the script's stdin and waits for it to exit, killing it if it takes too long.
+    fn drop( &mut self ) {
+        self.stdin = None;
+        if !matches!( self.child.wait_timeout( EXIT_TIMEOUT ), Ok( Some(_) )) {
+            self.child.kill().ok();
+            self.child.wait().ok();
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Cancellation Hearts, played by 6 to 10 players with two decks shuffled together.
+//!
+//! Identical cards discarded in the same trick cancel each other, and neither of them can win the trick. If all the
+//! cards of the suit led cancel, the trick is held over to the winner of the next trick, and the same player leads
+//! again. The cards left over after dealing go to the winner of the first trick. With two copies of each heart and of
+//! the Queen of Spades, shooting the moon takes all of them and scores 52 points. Players do not pass cards.
+
+use crate::*;
+
+use std::ops::{Range, RangeInclusive};
+
+/// The most players in one game of Cancellation Hearts.
+pub const MAX_CANCELLATION_PLAYERS: usize = 10;
+
+/// The numbers of players of Cancellation Hearts.
+pub const CANCELLATION_PLAYERS: RangeInclusive<usize> = 6..=MAX_CANCELLATION_PLAYERS;
+
+/// The actions that drive the Cancellation Hearts state, see `Action`.
+#[allow( clippy::large_enum_variant )]
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub enum CancellationAction {
+    /// Starts a new game. The scores are cleared while the times of winning are kept.
+    NewGame,
+    /// Deals cards to each player, and the cards left over to the kitty, see `CancellationState::deal_with()`.
+    Deal{ hands: [CountedCards;MAX_CANCELLATION_PLAYERS], kitty: CountedCards },
+    /// The player discards one card.
+    Discard{ who: usize, card: Cards },
+}
+
+/// The events resulting from applying `CancellationAction`s, see `Event`.
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub enum CancellationEvent {
+    /// A new game has started.
+    GameStarted,
+    /// The `deal`th hand of the game has been dealt.
+    Dealt{ deal: usize },
+    /// A new round is started by the early hand.
+    TrickStarted{ round: usize, leader: usize },
+    /// The player has discarded a card.
+    Discarded{ who: usize, card: Cards },
+    /// All the players have discarded in this round, and the winner took the cards worth `points`, including
+    /// the held over ones and the kitty, unless all the cards of the suit led cancelled.
+    TrickWon{ trick: CancellationTrick, points: i32 },
+    /// The player has taken all the copies of the penalty cards, and each other player scores their points.
+    ShotTheMoon{ who: usize },
+    /// All the rounds of this hand have been played.
+    HandScored{ hand_scores: [i32;MAX_CANCELLATION_PLAYERS], game_scores: [i32;MAX_CANCELLATION_PLAYERS] },
+    /// The game is over as `Ending` tells, and the player with the lowest score wins.
+    GameOver{ winner: usize },
+}
+
+/// The cards discarded in one round of Cancellation Hearts, see `Trick`.
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq )]
+pub struct CancellationTrick {
+    /// Which round it is, starting from 1.
+    pub round  : usize,
+    /// Who discarded the first card in this round.
+    pub leader : usize,
+    /// Whose card is the highest card not cancelled in this round, or `None` if all the cards of the suit led cancelled.
+    pub winner : Option<usize>,
+    /// The card discarded by each player, or `NO_CARD` if not discarded yet.
+    pub cards  : [Cards;MAX_CANCELLATION_PLAYERS],
+}
+
+/// The game state of Cancellation Hearts, driven by `CancellationState::apply()`.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = CancellationState::new( 8 );
+/// let ( hands, kitty ) = CancellationState::deal_with( 8, &mut Rng::with_seed( 2024 ));
+/// assert_eq!( hands[0].count(), 13 );
+/// assert!( kitty.is_empty() );
+///
+/// state.apply( CancellationAction::Deal{ hands, kitty }).unwrap();
+/// while state.phase() == Phase::Discarding {
+///     let who = state.next_to_discard();
+///     let card = state.pick_card( who );
+///     state.apply( CancellationAction::Discard{ who, card }).unwrap();
+/// }
+/// let scores = state.players().map( |who| state.game_score( who )).collect::<Vec<_>>();
+/// assert_eq!( scores, vec![ 0, 0, 6, 13, 7, 0, 0, 26 ]);
+/// assert_eq!( state.phase(), Phase::Dealing );
+/// ```
+#[derive( Clone, Debug )]
+pub struct CancellationState {
+    pub seats       : usize,
+    pub ending      : Ending,
+    pub rule_set    : RuleSet,
+        hands       : [CountedCards;MAX_CANCELLATION_PLAYERS],
+        taken       : [CountedCards;MAX_CANCELLATION_PLAYERS],
+        trick       : [Cards;MAX_CANCELLATION_PLAYERS],
+        hand_scores : [i32;MAX_CANCELLATION_PLAYERS],
+        game_scores : [i32;MAX_CANCELLATION_PLAYERS],
+        awards      : [u32;MAX_CANCELLATION_PLAYERS],
+        kitty       : CountedCards,
+        held_over   : CountedCards,
+        discarded   : Cards,
+        phase       : Phase,
+        deal        : usize,
+        round       : usize,
+        leader      : usize,
+        next        : usize,
+        winner      : Option<usize>,
+}
+
+impl CancellationState {
+    /// Constructs the game state for the given number of players, see `CANCELLATION_PLAYERS`.
+    pub fn new( seats: usize ) -> Self {
+        CancellationState {
+            seats       ,
+            ending      : Ending::default(),
+            rule_set    : RuleSet::default(),
+            hands       : [CountedCards::default();MAX_CANCELLATION_PLAYERS],
+            taken       : [CountedCards::default();MAX_CANCELLATION_PLAYERS],
+            trick       : [NO_CARD;MAX_CANCELLATION_PLAYERS],
+            hand_scores : [0;MAX_CANCELLATION_PLAYERS],
+            game_scores : [0;MAX_CANCELLATION_PLAYERS],
+            awards      : [0;MAX_CANCELLATION_PLAYERS],
+            kitty       : CountedCards::default(),
+            held_over   : CountedCards::default(),
+            discarded   : NO_CARD,
+            phase       : Phase::Dealing,
+            deal        : 0,
+            round       : 0,
+            leader      : ME,
+            next        : ME,
+            winner      : None,
+        }
+    }
+    /// Shuffles two decks with the given random number generator and deals them evenly among the players.
+    /// The cards left over are returned as the kitty.
+    pub fn deal_with( seats: usize, rng: &mut Rng ) -> ( [CountedCards;MAX_CANCELLATION_PLAYERS], CountedCards ) {
+        let mut deck = CountedCards::decks( 2 ).iter().collect::<Vec<_>>();
+        rng.shuffle( &mut deck );
+        let dealt = deck.len() / seats * seats;
+        let mut hands = [CountedCards::default();MAX_CANCELLATION_PLAYERS];
+        for ( i, &card ) in deck[ ..dealt ].iter().enumerate() {
+            hands[ i % seats ] += card;
+        }
+        let mut kitty = CountedCards::default();
+        deck[ dealt.. ].iter().for_each( |&card| kitty += card );
+        ( hands, kitty )
+    }
+    /// The seats of the players in this game.
+    pub fn players( &self ) -> Range<usize> {
+        ME..self.seats
+    }
+    /// How many rounds, i.e. tricks, are played in each hand.
+    pub fn rounds( &self ) -> usize {
+        CountedCards::decks( 2 ).count() / self.seats
+    }
+    /// The cards in the player's hand.
+    pub fn hand( &self, who: usize ) -> CountedCards {
+        self.hands[ who ]
+    }
+    /// The cards taken by the player in this hand.
+    pub fn taken( &self, who: usize ) -> CountedCards {
+        self.taken[ who ]
+    }
+    /// The player's score in this hand.
+    pub fn hand_score( &self, who: usize ) -> i32 {
+        self.hand_scores[ who ]
+    }
+    /// The player's score in this game.
+    pub fn game_score( &self, who: usize ) -> i32 {
+        self.game_scores[ who ]
+    }
+    /// Times of winning the game.
+    pub fn awards( &self, who: usize ) -> u32 {
+        self.awards[ who ]
+    }
+    /// The cards left over after dealing, which go to the winner of the first trick.
+    pub fn kitty( &self ) -> CountedCards {
+        self.kitty
+    }
+    /// The cards of the cancelled tricks, which go to the winner of the next trick.
+    pub fn held_over( &self ) -> CountedCards {
+        self.held_over
+    }
+    /// The cards discarded so far in current round.
+    pub fn trick( &self ) -> CancellationTrick {
+        CancellationTrick {
+            round  : self.round,
+            leader : self.leader,
+            winner : self.trick_winner(),
+            cards  : self.trick,
+        }
+    }
+    /// The step which this state is waiting for.
+    pub fn phase( &self ) -> Phase {
+        self.phase
+    }
+    /// Which round it is in this hand, starting from 1.
+    pub fn round( &self ) -> usize {
+        self.round
+    }
+    /// Who discarded, or will discard, the first card in this round.
+    pub fn leader( &self ) -> usize {
+        self.leader
+    }
+    /// The player to discard next.
+    pub fn next_to_discard( &self ) -> usize {
+        self.next
+    }
+    /// The winner of the game, or `None` if the game is not over.
+    pub fn winner( &self ) -> Option<usize> {
+        self.winner
+    }
+    /// The suit led in this round, or `NO_CARD` if the leader has not discarded yet.
+    pub fn suit_mask( &self ) -> Cards {
+        match self.trick[ self.leader ] {
+            NO_CARD => NO_CARD,
+            card    => card.suit_mask(),
+        }
+    }
+    /// Whose card wins this round so far, i.e. the highest card of the suit led not cancelled by an identical one.
+    pub fn trick_winner( &self ) -> Option<usize> {
+        let suit_mask = self.suit_mask();
+        let played = self.trick.iter().fold( CountedCards::default(), |mut played, &card| { played += card; played });
+        self.players()
+            .filter( |&i| self.trick[i] & suit_mask != NO_CARD && played.count_of( self.trick[i] ) == 1 )
+            .max_by_key( |&i| self.trick[i].0 )
+    }
+    /// Returns true if hearts may be led, see `GameState::hearts_may_lead()`.
+    pub fn hearts_may_lead( &self ) -> bool {
+        self.rule_set.hearts_anytime
+            || self.discarded.contains_suit( HEARTS )
+            || ( self.rule_set.queen_breaks && self.discarded.contains( QUEEN_OF_SPADES ))
+    }
+    /// Checks if the player discarding the card breaks any rule, see `GameState::break_the_rule()`.
+    pub fn break_the_rule( &self, who: usize, card: Cards ) -> Option<Rule> {
+        if self.phase != Phase::Discarding || who != self.next {
+            return Some( Rule::OutOfTurn );
+        }
+        if card.count() != 1 {
+            return Some( Rule::DiscardOneCard );
+        }
+        let hand = self.hands[ who ].distinct();
+        if !hand.contains( card ) {
+            return Some( Rule::DiscardInHand );
+        }
+        let suit_mask = if who == self.leader { NO_CARD } else { self.suit_mask() };
+        let opening_card = ( self.round == 1 ).then_some( TWO_OF_CLUBS );
+        self.rule_set.break_the_rule( hand, card, suit_mask, opening_card, self.hearts_may_lead() )
+    }
+    /// The built-in strategy to pick a card to discard, which never breaks the rules.
+    pub fn pick_card( &self, who: usize ) -> Cards {
+        let legal = self.hands[ who ].distinct().iter().filter( |&card| self.break_the_rule( who, card ).is_none() ).collect::<Vec<_>>();
+        let lowest_non_heart = legal.iter().copied().find( |card| !card.is_heart() );
+        if who == self.leader {
+            return lowest_non_heart.unwrap_or( legal[0] );
+        }
+        let suit_mask = self.suit_mask();
+        if legal.iter().all( |card| card.contains_suit( suit_mask )) { // follow suit
+            let winning = self.trick_winner().map( |i| self.trick[i] ).unwrap_or( NO_CARD );
+            let cancelled = |card: Cards| self.trick.contains( &card );
+            if let Some( &card ) = legal.iter().rev().find( |&&card| card < winning || cancelled( card )) {
+                return card;
+            }
+            let last = ( who+1 ) % self.seats == self.leader;
+            return if last { legal[ legal.len()-1 ] } else { legal[0] };
+        }
+        let penalty_cards = self.rule_set.penalty_cards();
+        legal.iter().copied().rev().find( |&card| penalty_cards.contains( card ) && !card.is_heart() ) // dump the Queen of Spades
+            .or_else( || legal.iter().copied().rev().find( |card| card.is_heart() ))
+            .unwrap_or_else( || legal.iter().copied().max_by_key( |card| card.suit_and_rank().1 ).unwrap() )
+    }
+    /// Changes the state by the action, or returns the rule that it breaks.
+    pub fn apply( &mut self, action: CancellationAction ) -> Result<Vec<CancellationEvent>, Rule> {
+        let mut events = Vec::new();
+        match action {
+            CancellationAction::NewGame => {
+                self.game_scores = [0;MAX_CANCELLATION_PLAYERS];
+                self.hand_scores = [0;MAX_CANCELLATION_PLAYERS];
+                self.deal = 0;
+                self.winner = None;
+                self.phase = Phase::Dealing;
+                events.push( CancellationEvent::GameStarted );
+            },
+            CancellationAction::Deal{ hands, kitty } => {
+                if self.phase != Phase::Dealing {
+                    return Err( Rule::OutOfTurn );
+                }
+                self.hands = hands;
+                self.kitty = kitty;
+                self.taken = [CountedCards::default();MAX_CANCELLATION_PLAYERS];
+                self.hand_scores = [0;MAX_CANCELLATION_PLAYERS];
+                self.held_over = CountedCards::default();
+                self.discarded = NO_CARD;
+                self.trick = [NO_CARD;MAX_CANCELLATION_PLAYERS];
+                self.deal += 1;
+                self.round = 1;
+                self.leader = self.players().find( |&i| self.hands[i].contains( TWO_OF_CLUBS )).unwrap_or( ME );
+                self.next = self.leader;
+                self.phase = Phase::Discarding;
+                events.push( CancellationEvent::Dealt{ deal: self.deal });
+                events.push( CancellationEvent::TrickStarted{ round: self.round, leader: self.leader });
+            },
+            CancellationAction::Discard{ who, card } => {
+                if let Some( rule ) = self.break_the_rule( who, card ) {
+                    return Err( rule );
+                }
+                self.hands[ who ].remove( card );
+                self.trick[ who ] = card;
+                self.discarded += card;
+                self.next = ( who+1 ) % self.seats;
+                events.push( CancellationEvent::Discarded{ who, card });
+                if self.next == self.leader {
+                    self.complete_trick( &mut events );
+                }
+            },
+        }
+        Ok( events )
+    }
+    fn complete_trick( &mut self, events: &mut Vec<CancellationEvent> ) {
+        let trick = self.trick();
+        let winner = trick.winner;
+        let mut cards = self.held_over;
+        self.trick.iter().for_each( |&card| cards += card );
+        if self.round == 1 {
+            cards += self.kitty;
+        }
+        let points = cards.iter().map( |card| self.rule_set.points_of( card )).sum();
+        self.held_over = CountedCards::default();
+        match winner {
+            Some( winner ) => {
+                self.taken[ winner ] += cards;
+                self.hand_scores[ winner ] += points;
+                self.leader = winner;
+            },
+            None => if self.round < self.rounds() {
+                self.held_over = cards;
+            },
+        }
+        events.push( CancellationEvent::TrickWon{ trick, points });
+
+        if self.round == self.rounds() {
+            self.score_hand( events );
+        } else {
+            self.round += 1;
+            self.trick = [NO_CARD;MAX_CANCELLATION_PLAYERS];
+            self.next = self.leader;
+            events.push( CancellationEvent::TrickStarted{ round: self.round, leader: self.leader });
+        }
+    }
+    fn score_hand( &mut self, events: &mut Vec<CancellationEvent> ) {
+        let penalty_cards = self.rule_set.penalty_cards();
+        let moon_points = 2 * self.rule_set.moon_points();
+        if let Some( who ) = self.players().find( |&i| penalty_cards.iter().all( |card| self.taken[i].count_of( card ) == 2 )) {
+            for i in self.players() {
+                self.hand_scores[i] += if i == who { -moon_points } else { moon_points };
+            }
+            events.push( CancellationEvent::ShotTheMoon{ who });
+        }
+        for i in self.players() {
+            self.game_scores[i] += self.hand_scores[i];
+        }
+        events.push( CancellationEvent::HandScored{ hand_scores: self.hand_scores, game_scores: self.game_scores });
+
+        let scores = self.players().map( |i| ( i, self.game_scores[i], self.hand_scores[i] )).collect::<Vec<_>>();
+        match self.ending.winner_of( self.deal, &scores ) {
+            Some( winner ) => {
+                self.winner = Some( winner );
+                self.awards[ winner ] += 1;
+                self.phase = Phase::GameOver;
+                events.push( CancellationEvent::GameOver{ winner });
+            },
+            None => self.phase = Phase::Dealing,
+        }
+    }
+}
@@ -1,5 +1,8 @@
 //! Cards definition of storing, comparing, passing, discarding, printing.
 
+use crate::player::MAX_PLAYERS;
+use fastrand::Rng;
+
 use std::{
     fmt::{self, Debug, Display, Formatter},
     ops::{Add, AddAssign, BitAnd, Not, Range, Sub},
@@ -248,7 +251,7 @@ impl Cards {
     /// ```
     pub fn parse_in_hand( input: &str, hand: Cards, suits: Cards ) -> Result<Cards, ParseError> {
         let mut cards = NO_CARD;
-        let mut chars = input.chars().rev().enumerate();
+        let chars = input.chars().rev().enumerate();
         let mut suit = NO_SUIT;
         let mut rank = NO_RANK;
 
@@ -264,7 +267,7 @@ impl Cards {
         let len = input.len();
         let mut ambiguous_cards = NO_CARD;
 
-        while let Some(( rev_nth, ch )) = chars.next() {
+        for ( rev_nth, ch ) in chars {
             match ch {
                 'c'|'C'|'♣' => suit = CLUB,
                 'd'|'D'|'♦' => suit = DIAMOND,
@@ -314,16 +317,64 @@ impl Cards {
             Ok( cards )
         }
     }
-    /// Shuffles a deck and deals cards to 4 players.
-    pub fn deal() -> [Self; 4] {
+    /// Shuffles a deck with the thread-global random number generator and deals cards to 4 players.
+    pub fn deal() -> [Self; MAX_PLAYERS] {
         let mut deck = CARDS;
         fastrand::shuffle( &mut deck );
-        [
-            deck[ 0..13].iter().fold( NO_CARD, |cards, &card| cards + card ),
-            deck[13..26].iter().fold( NO_CARD, |cards, &card| cards + card ),
-            deck[26..39].iter().fold( NO_CARD, |cards, &card| cards + card ),
-            deck[39..52].iter().fold( NO_CARD, |cards, &card| cards + card ),
-        ]
+        Cards::deal_deck( &deck, 4 )
+    }
+    /// Shuffles a deck with the given random number generator and deals cards to 4 players.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    /// let hands = Cards::deal_with( &mut Rng::with_seed( 2024 ));
+    /// assert_eq!( hands, Cards::deal_with( &mut Rng::with_seed( 2024 )));
+    /// assert_eq!( hands.iter().fold( NO_CARD, |deck, &hand| deck + hand ).count(), 52 );
+    /// ```
+    pub fn deal_with( rng: &mut Rng ) -> [Self; MAX_PLAYERS] {
+        Cards::deal_among( 4, rng )
+    }
+    /// Shuffles the deck for the given number of players with the given random number generator and deals
+    /// cards to them, see `Cards::deck_for()`. The hands of the seats not taken are `NO_CARD`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    /// let hands = Cards::deal_among( 5, &mut Rng::with_seed( 2024 ));
+    /// assert!( hands[..5].iter().all( |hand| hand.count() == 10 ));
+    /// assert_eq!( hands[5], NO_CARD );
+    /// ```
+    pub fn deal_among( seats: usize, rng: &mut Rng ) -> [Self; MAX_PLAYERS] {
+        let cards = Cards::deck_for( seats );
+        let mut deck = CARDS.into_iter().filter( |&card| cards.contains( card )).collect::<Vec<_>>();
+        rng.shuffle( &mut deck );
+        Cards::deal_deck( &deck, seats )
+    }
+    /// The cards to deal among the given number of players, 3~6, with the standard removals
+    /// so that each player gets the same number of cards:
+    ///
+    /// - 3 players, without 2♦;
+    /// - 4 players, all the 52 cards;
+    /// - 5 players, without 2♣ 2♦;
+    /// - 6 players, without 2♣ 3♣ 2♦ 3♦.
+    pub fn deck_for( seats: usize ) -> Cards {
+        let deck = CLUBS + DIAMONDS + SPADES + HEARTS;
+        match seats {
+            3 => deck - TWO_OF_DIAMONDS,
+            5 => deck - TWO_OF_CLUBS - TWO_OF_DIAMONDS,
+            6 => deck - TWO_OF_CLUBS - THREE_OF_CLUBS - TWO_OF_DIAMONDS - THREE_OF_DIAMONDS,
+            _ => deck,
+        }
+    }
+    fn deal_deck( deck: &[Cards], seats: usize ) -> [Self; MAX_PLAYERS] {
+        let mut hands = [NO_CARD; MAX_PLAYERS];
+        deck.chunks( deck.len() / seats ).zip( &mut hands ).for_each( |( chunk, hand )| {
+            *hand = chunk.iter().fold( NO_CARD, |cards, &card| cards + card );
+        });
+        hands
     }
     #[inline]
     /// Computates the result of passing cards / discarding card.
@@ -352,13 +403,30 @@ impl Cards {
     }
     /// Computes the result of picks three cards for passing.
     ///
-    /// Currently the stategy of this is naive, regardless of hitting the moon.
+    /// The Queen of Spades and the highest cards are passed, see `Cards::pick_three_cards_to()` for the suits
+    /// in hand and the pass direction taken into account, and `Cards::pick_three_cards_for_moon()` for the hands
+    /// to shoot the moon.
     pub fn pick_three_cards( self ) -> Transfer {
+        self.pick_three_cards_keeping( NO_CARD )
+    }
+    /// Computes the result of picks three cards for passing, other than the cards to keep,
+    /// e.g. the Jack of Diamonds which counts -10 points.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let hand: Cards = "23c 2d JKAd 2s 23h".parse().unwrap();
+    /// assert_eq!( hand.pick_three_cards().dest, "JKAd".parse().unwrap() );
+    /// assert_eq!( hand.pick_three_cards_keeping( JACK_OF_DIAMONDS ).dest, "KAd 3h".parse().unwrap() );
+    /// ```
+    pub fn pick_three_cards_keeping( self, keep: Cards ) -> Transfer {
         let mut src  = self;
         let mut dest = NO_CARD;
         let mut passed = 0;
 
-        if let Some( transfered ) = self.transfer( QUEEN_OF_SPADES ) {
+        if let Some( transfered ) = self.transfer( QUEEN_OF_SPADES ).filter( |_| !keep.contains( QUEEN_OF_SPADES )) {
             src  = transfered.src;
             dest = transfered.dest;
             passed += 1;
@@ -366,7 +434,11 @@ impl Cards {
 
         for rank in RANKS.rev() {
             for suit in [HEART, SPADE, DIAMOND, CLUB] {
-                if let Some( transfered ) = (Transfer{ src, dest }).transfer( Cards::of( rank, suit )) {
+                let card = Cards::of( rank, suit );
+                if keep.contains( card ) {
+                    continue;
+                }
+                if let Some( transfered ) = (Transfer{ src, dest }).transfer( card ) {
                     src  = transfered.src;
                     dest = transfered.dest;
                     passed += 1;
@@ -379,6 +451,109 @@ impl Cards {
 
         Transfer{ src, dest } //unreachable!()
     }
+    /// Computes the result of picks three cards for passing, other than the cards to keep, by the suits in hand
+    /// and whether the cards go to the left player, who discards right after.
+    ///
+    /// The Queen of Spades is kept if guarded by at least 3 lower spades when passing to the left, who could drop it
+    /// on the Ace or King, or by at least 4 otherwise. If not kept, it is passed, with the Ace and King of Spades
+    /// when short of lower spades. Then the clubs or diamonds are passed if it voids the suit, the shortest first,
+    /// and the highest cards fill the rest, guards of the kept Queen excluded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let hand: Cards = "2c 2345d 234QKAs 23h".parse().unwrap();
+    /// assert_eq!( hand.pick_three_cards_to( NO_CARD, true  ).dest, "2c KAs".parse().unwrap() );
+    /// assert_eq!( hand.pick_three_cards_to( NO_CARD, false ).dest, "2c QAs".parse().unwrap() );
+    ///
+    /// let hand: Cards = "234c 2345d 2QKAs 23h".parse().unwrap();
+    /// assert_eq!( hand.pick_three_cards_to( NO_CARD, false ).dest, "QKAs".parse().unwrap() );
+    /// ```
+    pub fn pick_three_cards_to( self, keep: Cards, to_left: bool ) -> Transfer {
+        fn pass( passing: &mut Transfer, cards: Cards ) {
+            for card in cards.iter().rev() {
+                if passing.dest.count() < 3 && passing.src.contains( card ) {
+                    passing.src = passing.src - card;
+                    passing.dest += card;
+                }
+            }
+        }
+        let mut passing = Transfer{ src: self, dest: NO_CARD };
+        let guards = ( self & SPADES ).iter().filter( |&card| card < QUEEN_OF_SPADES ).fold( NO_CARD, |cards, card| cards + card );
+        let enough = if to_left { 3 } else { 4 };
+        let keep_queen = keep.contains( QUEEN_OF_SPADES ) || ( self.contains( QUEEN_OF_SPADES ) && guards.count() >= enough );
+
+        if !keep_queen {
+            pass( &mut passing, self & QUEEN_OF_SPADES );
+            if guards.count() < 3 {
+                pass( &mut passing, self & ( ACE_OF_SPADES + KING_OF_SPADES - keep ));
+            }
+        }
+
+        while let Some( void ) = [CLUBS, DIAMONDS].into_iter()
+            .map( |suit| passing.src & suit )
+            .filter( |&cards| cards != NO_CARD && cards.count() + passing.dest.count() <= 3 && cards & keep == NO_CARD )
+            .min_by_key( |cards| cards.count() )
+        {
+            pass( &mut passing, void );
+        }
+
+        let guarded = if keep_queen { guards } else { NO_CARD };
+        for spare in [ guarded, NO_CARD ] {
+            for rank in RANKS.rev() {
+                for suit in [HEART, SPADE, DIAMOND, CLUB] {
+                    pass( &mut passing, Cards::of( rank, suit ) - keep - spare );
+                }
+            }
+        }
+        passing
+    }
+    /// Checks if the hand is strong enough to shoot the moon: more than half of its cards are Jacks or higher,
+    /// and no unseen heart is higher than the hearts in hand.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let hand: Cards = "2c QKAd JQKAs 3JQKAh".parse().unwrap();
+    /// assert!( hand.may_shoot_the_moon( Cards::deck_for( 4 ) - hand ));
+    ///
+    /// let hand: Cards = "23456c 2KAd 2QKs 3Kh".parse().unwrap();
+    /// assert!( !hand.may_shoot_the_moon( Cards::deck_for( 4 ) - hand ));
+    /// ```
+    pub fn may_shoot_the_moon( self, unseen: Cards ) -> bool {
+        let high_cards = self.iter().filter( |card| card.suit_and_rank().1 >= JACK ).count();
+        let highest_heart = |cards: Cards| ( cards & HEARTS ).iter().next_back().unwrap_or_default();
+        high_cards * 2 > self.count() as usize && highest_heart( unseen ) <= highest_heart( self )
+    }
+    /// Computes the result of picks three cards for passing, to shoot the moon:
+    /// the lowest cards other than the cards to keep.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let hand: Cards = "2c QKAd JQKAs 3JQKAh".parse().unwrap();
+    /// assert_eq!( hand.pick_three_cards_for_moon( NO_CARD ).dest, "2c 3h Js".parse().unwrap() );
+    /// ```
+    pub fn pick_three_cards_for_moon( self, keep: Cards ) -> Transfer {
+        let mut src  = self;
+        let mut dest = NO_CARD;
+        for rank in RANKS {
+            for suit in [CLUB, DIAMOND, SPADE, HEART] {
+                let card = Cards::of( rank, suit );
+                if dest.count() < 3 && src.contains( card ) && !keep.contains( card ) {
+                    src = src - card;
+                    dest += card;
+                }
+            }
+        }
+        Transfer{ src, dest }
+    }
     /// Computes this card's suit.
     pub fn suit_mask( self ) -> Cards {
         if self < TWO_OF_DIAMONDS {
@@ -539,6 +714,7 @@ impl Add<Self> for Cards {
     type Output = Self;
 
     #[inline]
+    #[allow( clippy::suspicious_arithmetic_impl )]
     fn add( self, other: Cards ) -> Self {
         Cards( self.0 | other.0 )
     }
@@ -546,6 +722,7 @@ impl Add<Self> for Cards {
 
 impl AddAssign<Self> for Cards {
     #[inline]
+    #[allow( clippy::suspicious_op_assign_impl )]
     fn add_assign( &mut self, other: Cards ) {
         self.0 |= other.0;
     }
@@ -596,41 +773,46 @@ impl Cards {
     /// assert_eq!( cards.text(LOWERCASE_RANK), "467qa♣ 480♦ 9k♠ 6qk♥" );
     /// ```
     pub fn text( &self, flags: u32 ) -> String {
-        let mut result = String::new();
-
-        let mask = Mask( flags );
-        let use_ascii_suits = mask.matches( ASCII_SUIT );
-        let suit_chars = SUIT_CHARS[ use_ascii_suits as usize ];
-        let rank_chars = RANK_CHARS[ mask.matches( LOWERCASE_RANK ) as usize ];
-        let mut last_suit = NO_SUIT;
-        for suit in [CLUB, DIAMOND, SPADE, HEART] {
-            for rank in TWO..(TWO+13) {
-                if ( self.0 >> (suit+rank) ) & 1 == 1 {
-                    if !mask.matches( NO_SHARED_SUIT ) && last_suit != suit  {
-                        if last_suit != NO_SUIT {
-                            result.push( suit_chars[ (last_suit>>4) as usize ]);
-                            if !mask.matches( NO_SPACES ) { result.push( ' ' )}
-                        }
-                        last_suit = suit;
-                    }
-                    result.push( rank_chars[ (rank-TWO) as usize ]);
-                    if mask.matches( NO_SHARED_SUIT ) {
-                        result.push( suit_chars[ (suit>>4) as usize ]);
+        text_of_counts( |suit, rank| (( self.0 >> (suit+rank) ) & 1 ) as u8, flags )
+    }
+}
+
+/// Prints the cards as `Cards::text()` does, each of which repeats as many times as `count` tells.
+fn text_of_counts( count: impl Fn(u8,u8)->u8, flags: u32 ) -> String {
+    let mut result = String::new();
+
+    let mask = Mask( flags );
+    let use_ascii_suits = mask.matches( ASCII_SUIT );
+    let suit_chars = SUIT_CHARS[ use_ascii_suits as usize ];
+    let rank_chars = RANK_CHARS[ mask.matches( LOWERCASE_RANK ) as usize ];
+    let mut last_suit = NO_SUIT;
+    for suit in [CLUB, DIAMOND, SPADE, HEART] {
+        for rank in TWO..(TWO+13) {
+            for _ in 0..count( suit, rank ) {
+                if !mask.matches( NO_SHARED_SUIT ) && last_suit != suit  {
+                    if last_suit != NO_SUIT {
+                        result.push( suit_chars[ (last_suit>>4) as usize ]);
                         if !mask.matches( NO_SPACES ) { result.push( ' ' )}
                     }
+                    last_suit = suit;
+                }
+                result.push( rank_chars[ (rank-TWO) as usize ]);
+                if mask.matches( NO_SHARED_SUIT ) {
+                    result.push( suit_chars[ (suit>>4) as usize ]);
+                    if !mask.matches( NO_SPACES ) { result.push( ' ' )}
                 }
             }
         }
-        if last_suit != NO_SUIT {
-            result.push( suit_chars[ (last_suit>>4) as usize ]);
-        }
-
-        if result.ends_with(' ') {
-            result.pop();
-        }
+    }
+    if last_suit != NO_SUIT {
+        result.push( suit_chars[ (last_suit>>4) as usize ]);
+    }
 
-        result
+    if result.ends_with(' ') {
+        result.pop();
     }
+
+    result
 }
 
 fn fmt_to_mask( f: &Formatter ) -> u32 {
@@ -680,6 +862,114 @@ impl Transfer {
     }
 }
 
+/// A multiset of cards, which may hold duplicated cards, e.g. two decks shuffled together in Cancellation Hearts.
+///
+/// # Example
+///
+/// ```
+/// use heartless::*;
+///
+/// let mut hand = CountedCards::from( "2Qc 3h".parse::<Cards>().unwrap() );
+/// hand += TWO_OF_CLUBS;
+/// assert_eq!( hand.count(), 4 );
+/// assert_eq!( hand.count_of( TWO_OF_CLUBS ), 2 );
+/// assert_eq!( hand.text( ASCII_SUIT ), "22Qc 3h" );
+///
+/// assert!( hand.remove( TWO_OF_CLUBS ));
+/// assert_eq!( hand.distinct(), "2Qc 3h".parse::<Cards>().unwrap() );
+/// assert_eq!( CountedCards::decks( 2 ).count(), 104 );
+/// ```
+#[derive( Copy, Clone, PartialEq, Eq )]
+pub struct CountedCards( [u8;52] );
+
+impl Default for CountedCards {
+    fn default() -> Self {
+        CountedCards( [0;52] )
+    }
+}
+
+impl CountedCards {
+    /// The given number of full decks shuffled together.
+    pub fn decks( n: u8 ) -> Self {
+        CountedCards( [n;52] )
+    }
+    /// How many copies of the card it holds.
+    pub fn count_of( &self, card: Cards ) -> u8 {
+        self.0[ card.deck_index() ]
+    }
+    /// How many cards it holds, counting the duplicated ones.
+    pub fn count( &self ) -> usize {
+        self.0.iter().map( |&n| n as usize ).sum()
+    }
+    /// Returns true if it holds at least one copy of each of the cards.
+    pub fn contains( &self, cards: Cards ) -> bool {
+        cards.iter().all( |card| self.count_of( card ) > 0 )
+    }
+    /// Returns true if it holds no cards.
+    pub fn is_empty( &self ) -> bool {
+        self.0.iter().all( |&n| n == 0 )
+    }
+    /// The cards it holds, regardless of the number of copies.
+    pub fn distinct( &self ) -> Cards {
+        CARDS.into_iter().filter( |&card| self.count_of( card ) > 0 ).fold( NO_CARD, |cards, card| cards + card )
+    }
+    /// Removes one copy of the card, or returns false if it does not hold one.
+    pub fn remove( &mut self, card: Cards ) -> bool {
+        let n = &mut self.0[ card.deck_index() ];
+        if *n == 0 {
+            false
+        } else {
+            *n -= 1;
+            true
+        }
+    }
+    /// All the cards in ascending order, each repeated as many times as the copies it holds.
+    pub fn iter( &self ) -> impl Iterator<Item=Cards> + '_ {
+        CARDS.iter().flat_map( |&card| std::iter::repeat_n( card, self.count_of( card ) as usize ))
+    }
+    /// The string representation, in which duplicated cards repeat, see `Cards::text()`.
+    pub fn text( &self, flags: u32 ) -> String {
+        text_of_counts( |suit, rank| self.count_of( Cards::of( rank, suit )), flags )
+    }
+}
+
+impl From<Cards> for CountedCards {
+    fn from( cards: Cards ) -> Self {
+        let mut counted = CountedCards::default();
+        counted += cards;
+        counted
+    }
+}
+
+impl AddAssign<Cards> for CountedCards {
+    /// Adds one copy of each of the cards.
+    fn add_assign( &mut self, cards: Cards ) {
+        for card in cards.iter() {
+            self.0[ card.deck_index() ] += 1;
+        }
+    }
+}
+
+impl AddAssign<Self> for CountedCards {
+    fn add_assign( &mut self, other: Self ) {
+        for ( n, m ) in self.0.iter_mut().zip( other.0 ) {
+            *n += m;
+        }
+    }
+}
+
+impl Debug for CountedCards {
+    fn fmt( &self, f: &mut Formatter ) -> fmt::Result {
+        write!( f, "{}", self.text( fmt_to_mask(f) | NO_SHARED_SUIT ))
+    }
+}
+
+impl Display for CountedCards {
+    fn fmt( &self, f: &mut Formatter ) -> fmt::Result {
+        write!( f, "{}", self.text( fmt_to_mask(f) ))
+    }
+}
+
 /// A deck of cards.
 pub const CARDS: [Cards; 52] = [
       TWO_OF_CLUBS   ,
@@ -737,7 +1027,7 @@ pub const CARDS: [Cards; 52] = [
 ];
 
 /// Lowercase names of cards. Both the ranks and suits are lowercases.
-pub const LNAMES: [&'static str; 52] = [
+pub const LNAMES: [&str; 52] = [
     "2c", "3c", "4c", "5c", "6c", "7c", "8c", "9c", "0c", "jc", "qc", "kc", "ac",
     "2d", "3d", "4d", "5d", "6d", "7d", "8d", "9d", "0d", "jd", "qd", "kd", "ad",
     "2s", "3s", "4s", "5s", "6s", "7s", "8s", "9s", "0s", "js", "qs", "ks", "as",
@@ -745,7 +1035,7 @@ pub const LNAMES: [&'static str; 52] = [
 ];
 
 /// Lowercase names of cards. Both the ranks of Jack, Queen, King, Ace are uppercases.
-pub const UNAMES: [&'static str; 52] = [
+pub const UNAMES: [&str; 52] = [
     "2c", "3c", "4c", "5c", "6c", "7c", "8c", "9c", "0c", "Jc", "Qc", "Kc", "Ac",
     "2d", "3d", "4d", "5d", "6d", "7d", "8d", "9d", "0d", "Jd", "Qd", "Kd", "Ad",
     "2s", "3s", "4s", "5s", "6s", "7s", "8s", "9s", "0s", "Js", "Qs", "Ks", "As",
@@ -753,7 +1043,7 @@ pub const UNAMES: [&'static str; 52] = [
 ];
 
 /// Graphic names of cards. The suits are "graphic chars".
-pub const GNAMES: [&'static str; 52] = [
+pub const GNAMES: [&str; 52] = [
     "2♣'", "3♣'", "4♣'", "5♣'", "6♣'", "7♣'", "8♣'", "9♣'", "0♣'", "J♣'", "Q♣'", "K♣'", "A♣'",
     "2♦'", "3♦'", "4♦'", "5♦'", "6♦'", "7♦'", "8♦'", "9♦'", "0♦'", "J♦'", "Q♦'", "K♦'", "A♦'",
     "2♠'", "3♠'", "4♠'", "5♠'", "6♠'", "7♠'", "8♠'", "9♠'", "0♠'", "J♠'", "Q♠'", "K♠'", "A♠'",
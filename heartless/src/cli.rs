@@ -1,5 +1,18 @@
 //! Commandline interface and program configuration.
 
+use crate::{
+    bot::ScriptMode,
+    card::{CLUBS, Cards},
+    game::{BUILTIN_ISMCTS, BUILTIN_PIMC},
+    ismcts::DEFAULT_ISMCTS_ITERATIONS,
+    pimc::DEFAULT_PIMC_SAMPLES,
+    solver::DEFAULT_ANALYZE_TRICKS,
+    cancellation::{CANCELLATION_PLAYERS, MAX_CANCELLATION_PLAYERS},
+    player::{LEFT, MAX_PLAYERS, ME},
+    rule_set::RulePack,
+    state::{MoonRule, Partnership, PassSchedule, TieBreak},
+};
+
 use std::{
     ffi::OsString,
     path::PathBuf
@@ -15,28 +28,95 @@ struct Cli {
     no_shared_suit  : bool,
     #[arg(long, help("No whitespaces in between cards"))]
     no_spaces       : bool,
-    #[arg(long, help("Script for the left,opposite,right players, unless otherwise specified"))]
+    #[arg(long, help("Script or built-in strategy, e.g. \"builtin:pimc\" or \"builtin:ismcts\", for the left,opposite,right players, unless otherwise specified"))]
     others          : Option<PathBuf>,
-    #[arg(long, help("Script for me to hint/decide which card(s) to pass/discard, see --automatic"))]
+    #[arg(long, help("Script or built-in strategy for me to hint/decide which card(s) to pass/discard, see --automatic"))]
     me              : Option<PathBuf>,
-    #[arg(long, help("Script for the left player"))]
+    #[arg(long, help("Script or built-in strategy for the left player"))]
     left            : Option<PathBuf>,
-    #[arg(long, help("Script for the opposite player"))]
+    #[arg(long, help("Script or built-in strategy for the opposite player, or the players between the left and right players with 5 or 6 players"))]
     opposite        : Option<PathBuf>,
-    #[arg(long, help("Script for the right player"))]
+    #[arg(long, help("Script or built-in strategy for the right player, i.e. the last seat"))]
     right           : Option<PathBuf>,
-    #[arg(long, help("Script for all players including me, unless otherwise specified"))]
+    #[arg(long, help("Script or built-in strategy for all players including me, unless otherwise specified"))]
     all             : Option<PathBuf>,
+    #[arg(long, default_value_t = DEFAULT_PIMC_SAMPLES, help("How many samples of hidden hands \"builtin:pimc\" draws for each card"))]
+    pimc_samples    : usize,
+    #[arg(long, help("Time budget in milliseconds of \"builtin:pimc\" for each card, regardless of --pimc-samples"))]
+    pimc_millis     : Option<u64>,
+    #[arg(long, default_value_t = DEFAULT_ISMCTS_ITERATIONS, help("How many iterations \"builtin:ismcts\" runs for each card"))]
+    ismcts_iters    : usize,
+    #[arg(long, help("Time budget in milliseconds of \"builtin:ismcts\" for each card, regardless of --ismcts-iters"))]
+    ismcts_millis   : Option<u64>,
     #[arg(long, help("Provide a random seed"))]
     seed            : Option<u64>,
     #[arg(long, help("For how many times playing the games"))]
     count           : Option<u32>,
     #[arg(long, help("Timout in milliseconds for scripting"))]
     timeout         : Option<u64>,
+    #[arg(long, value_enum, default_value_t, help("Run scripts for each pass/card with arguments, or once per game talking JSON Lines over stdin/stdout"))]
+    script_mode     : ScriptMode,
     #[arg(long, help("Don't delay output of each hand"))]
     impatient       : Option<bool>,
     #[arg(long, help("No user input, use script given by '--me'/'-all' or built-in strategy for me"))]
     pub automatic   : bool,
+    #[arg(long, help("Print game events in JSON Lines instead of console text"))]
+    json            : bool,
+    #[arg(long, help("Resume the game saved by the \"save\" command"))]
+    load            : Option<PathBuf>,
+    #[arg(long, help("The game is over when some player hits this score or higher, 100 by default"))]
+    target_score    : Option<i32>,
+    #[arg(long, value_parser = clap::value_parser!( u64 ).range( 1.. ), help("Play a fixed number of hands in each game, regardless of the target score"))]
+    hands           : Option<u64>,
+    #[arg(long, value_enum, default_value_t, help("How to choose the winner among the players sharing the lowest score"))]
+    tie_break       : TieBreak,
+    #[arg(long, value_enum, default_value_t, help("The variant of Hearts telling how many points each card counts"))]
+    rule_pack       : RulePack,
+    #[arg(long, help("The Jack of Diamonds counts -10 points"))]
+    jack_of_diamonds: bool,
+    #[arg(long, help("Hearts and the Queen of Spades may be discarded in the first trick"))]
+    points_on_first : bool,
+    #[arg(long, help("Discarding the Queen of Spades breaks hearts"))]
+    queen_breaks    : bool,
+    #[arg(long, help("Hearts may be led at any time, even before hearts are broken"))]
+    hearts_anytime  : bool,
+    #[arg(long, help("The first trick may be led by any club, or any card of the opening card's suit"))]
+    any_opening_suit: bool,
+    #[arg(long, value_enum, default_value_t, help("How to score the hand when a player shoots the moon"))]
+    moon_rule       : MoonRule,
+    #[arg(long, help("Taking all the 13 tricks scores 52 points instead of 26"))]
+    shoot_the_sun   : bool,
+    #[arg(long, value_enum, default_value_t, help("To whom the players pass cards in each hand"))]
+    pass_schedule   : PassSchedule,
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!( u8 ).range( 3..=MAX_CANCELLATION_PLAYERS as i64 ),
+        help("The number of players, 3~6, removing low cards so that everyone gets the same number of cards"))]
+    players         : u8,
+    #[arg(long, help("Play Cancellation Hearts with two decks for 6~10 players, see --players"))]
+    cancellation    : bool,
+    #[arg(long, value_parser = parse_card, help("The card to lead the first trick, the lowest club in the deck by default"))]
+    opening_card    : Option<Cards>,
+    #[arg(long, value_enum, default_value_t, help("Partners sitting opposite play as teams, shooting the moon alone or together"))]
+    partnership     : Partnership,
+    #[arg(long, help("Disable the \"undo\" command, e.g. for competitive play"))]
+    no_undo         : bool,
+    #[command(subcommand)]
+    command         : Option<Command>,
+}
+
+#[derive( clap::Subcommand )]
+enum Command {
+    #[command(about("Step through a game exported by the \"export\" command trick by trick"))]
+    Replay {
+        #[arg(help("The replay file"))]
+        file        : PathBuf,
+    },
+    #[command(about("Solve the last tricks of each hand in a game exported by the \"export\" command with all hands known"))]
+    Analyze {
+        #[arg(help("The replay file"))]
+        file        : PathBuf,
+        #[arg(long, default_value_t = DEFAULT_ANALYZE_TRICKS, help("How many tricks at the end of each hand to solve"))]
+        tricks      : usize,
+    },
 }
 
 /// The configuration of heartless game.
@@ -54,9 +134,9 @@ pub struct Config {
     pub me              : Option<PathBuf>,
     /// The path of AI script for the left player
     pub left            : Option<PathBuf>,
-    /// The path of AI script for the opposite player
+    /// The path of AI script for the opposite player, or the players in between the left and right players
     pub opposite        : Option<PathBuf>,
-    /// The path of AI script for the right player
+    /// The path of AI script for the right player, i.e. the last seat
     pub right           : Option<PathBuf>,
     /// The initial random seed to get determined dealing
     pub seed            : Option<u64>,
@@ -64,19 +144,141 @@ pub struct Config {
     pub count           : Option<u32>,
     /// The timeout milliseconds for AI scripts
     pub timeout         : Option<u64>,
+    /// How the AI scripts are run
+    pub script_mode     : ScriptMode,
+    /// How many samples of hidden hands the Perfect Information Monte Carlo strategy draws for each card
+    pub pimc_samples    : usize,
+    /// The time budget in milliseconds of the Perfect Information Monte Carlo strategy for each card
+    pub pimc_millis     : Option<u64>,
+    /// How many iterations the Information Set Monte Carlo Tree Search strategy runs for each card
+    pub ismcts_iters    : usize,
+    /// The time budget in milliseconds of the Information Set Monte Carlo Tree Search strategy for each card
+    pub ismcts_millis   : Option<u64>,
     /// Don't delay output of each hand
     pub impatient       : Option<bool>,
     /// No user input required
     pub automatic       : bool,
+    /// Prints game events in JSON Lines instead of console text, see `json` module
+    pub json            : bool,
+    /// The path of the saved game to resume
+    pub load            : Option<PathBuf>,
+    /// The game is over when some player hits this score or higher
+    pub target_score    : i32,
+    /// Plays a fixed number of hands in each game instead of playing to the target score
+    pub hands           : Option<usize>,
+    /// How to choose the winner among the players sharing the lowest score
+    pub tie_break       : TieBreak,
+    /// The variant of Hearts telling how many points each card counts
+    pub rule_pack       : RulePack,
+    /// The Jack of Diamonds counts -10 points
+    pub jack_of_diamonds: bool,
+    /// Hearts and other penalty cards may be discarded in the first trick
+    pub points_on_first : bool,
+    /// Discarding the Queen of Spades breaks hearts
+    pub queen_breaks    : bool,
+    /// Hearts may be led at any time
+    pub hearts_anytime  : bool,
+    /// The first trick may be led by any card of the opening card's suit
+    pub any_opening_suit: bool,
+    /// How to score the hand when a player shoots the moon
+    pub moon_rule       : MoonRule,
+    /// Taking all the 13 tricks scores 52 points instead of 26
+    pub shoot_the_sun   : bool,
+    /// To whom the players pass cards in each hand
+    pub pass_schedule   : PassSchedule,
+    /// The number of players, 3~6, or 6~10 for Cancellation Hearts
+    pub players         : usize,
+    /// Plays Cancellation Hearts with two decks shuffled together
+    pub cancellation    : bool,
+    /// The card to lead the first trick
+    pub opening_card    : Cards,
+    /// Whether partners sitting opposite play as teams
+    pub partnership     : Partnership,
+    /// Disallows taking back the human player's passes and discards
+    pub no_undo         : bool,
+    /// The path of the replay file to step through instead of playing
+    pub replay          : Option<PathBuf>,
+    /// The path of the replay file to analyze by the double-dummy solver instead of playing
+    pub analyze         : Option<PathBuf>,
+    /// How many tricks at the end of each hand to analyze
+    pub analyze_tricks  : usize,
+}
+
+impl Default for Config {
+    /// The configuration given no arguments.
+    fn default() -> Self {
+        Config::from_args([ "heartless" ])
+    }
 }
 
 impl Config {
+    /// The script or built-in strategy of the player: "--me", "--left", "--right" for the last seat, and
+    /// "--opposite" for every seat in between.
+    pub fn script_of( &self, who: usize ) -> Option<&PathBuf> {
+        match who {
+            ME                             => self.me      .as_ref(),
+            LEFT                           => self.left    .as_ref(),
+            who if who + 1 == self.players => self.right   .as_ref(),
+            who if who < self.players      => self.opposite.as_ref(),
+            _                              => None,
+        }
+    }
     pub fn from_args<I,T>( iter: I ) -> Self
         where I: IntoIterator<Item=T>
             , T: Into<OsString> + Clone
     {
         let cli = <Cli as clap::Parser>::parse_from( iter );
 
+        let conflict = |message: &str| -> ! {
+            <Cli as clap::CommandFactory>::command().error( clap::error::ErrorKind::ArgumentConflict, message ).exit()
+        };
+        let players = cli.players as usize;
+        let scripts = [ &cli.me, &cli.left, &cli.opposite, &cli.right, &cli.others, &cli.all ];
+        if cli.cancellation {
+            if !CANCELLATION_PLAYERS.contains( &players ) {
+                conflict( &format!( "Cancellation Hearts is for 6~10 players, not {players}" ));
+            }
+            let unsupported = [
+                ( cli.json                                         , "--json"          ),
+                ( cli.load.is_some()                               , "--load"          ),
+                ( cli.opening_card.is_some()                       , "--opening-card"  ),
+                ( cli.pass_schedule != PassSchedule::default()     , "--pass-schedule" ),
+                ( cli.moon_rule != MoonRule::default()             , "--moon-rule"     ),
+                ( cli.shoot_the_sun                                , "--shoot-the-sun" ),
+                ( cli.partnership != Partnership::Off              , "--partnership"   ),
+                ( scripts.iter().any( |script| script.is_some() )  , "a script"        ),
+            ];
+            if let Some(( _, arg )) = unsupported.iter().find( |( used, _ )| *used ) {
+                conflict( &format!( "{arg} is not supported in Cancellation Hearts" ));
+            }
+        } else if players > MAX_PLAYERS {
+            conflict( &format!( "{players} players need --cancellation, otherwise 3~6 players" ));
+        }
+        let deck = if cli.cancellation { Cards::deck_for( 4 ) } else { Cards::deck_for( players ) };
+        let opening_card = cli.opening_card.unwrap_or_else( || ( deck & CLUBS ).iter().next().unwrap_or_default() );
+        if !deck.contains( opening_card ) {
+            conflict( &format!( "the opening card {opening_card} is removed from the deck for {players} players" ));
+        }
+        if !players.is_multiple_of( 2 ) && cli.partnership != Partnership::Off {
+            conflict( &format!( "partners cannot sit opposite with {players} players" ));
+        }
+        if matches!( cli.command, Some( Command::Analyze{ .. })) && ( cli.json || cli.cancellation ) {
+            conflict( "analyze supports neither --json nor --cancellation" );
+        }
+        if let Some( builtin ) = scripts.iter().filter_map( |script| script.as_ref()?.to_str() )
+            .find( |&script| script.starts_with( "builtin:" ) && ![ BUILTIN_PIMC, BUILTIN_ISMCTS ].contains( &script ))
+        {
+            conflict( &format!( "unknown built-in strategy {builtin}, expecting {BUILTIN_PIMC} or {BUILTIN_ISMCTS}" ));
+        }
+        if players != 4 && cli.pass_schedule == PassSchedule::Scatter {
+            conflict( "the scatter pass is only for 4 players" );
+        }
+        let ( replay, analyze, analyze_tricks ) = match cli.command {
+            Some( Command::Replay { file         }) => ( Some( file ), None        , DEFAULT_ANALYZE_TRICKS ),
+            Some( Command::Analyze{ file, tricks }) => ( None        , Some( file ), tricks                 ),
+            None                                    => ( None        , None        , DEFAULT_ANALYZE_TRICKS ),
+        };
+
         Config {
             ascii_suit      : cli.ascii_suit,
             lowercase_rank  : cli.lowercase_rank,
@@ -89,8 +291,42 @@ impl Config {
             seed            : cli.seed,
             count           : cli.count,
             timeout         : cli.timeout,
+            script_mode     : cli.script_mode,
+            pimc_samples    : cli.pimc_samples,
+            pimc_millis     : cli.pimc_millis,
+            ismcts_iters    : cli.ismcts_iters,
+            ismcts_millis   : cli.ismcts_millis,
             impatient       : cli.impatient,
             automatic       : cli.automatic,
+            json            : cli.json,
+            load            : cli.load,
+            target_score    : cli.target_score.unwrap_or( 100 ),
+            hands           : cli.hands.map( |hands| hands as usize ),
+            tie_break       : cli.tie_break,
+            rule_pack       : cli.rule_pack,
+            jack_of_diamonds: cli.jack_of_diamonds,
+            points_on_first : cli.points_on_first,
+            queen_breaks    : cli.queen_breaks,
+            hearts_anytime  : cli.hearts_anytime,
+            any_opening_suit: cli.any_opening_suit,
+            moon_rule       : cli.moon_rule,
+            shoot_the_sun   : cli.shoot_the_sun,
+            pass_schedule   : cli.pass_schedule,
+            players         ,
+            cancellation    : cli.cancellation,
+            opening_card    ,
+            partnership     : cli.partnership,
+            no_undo         : cli.no_undo,
+            replay          ,
+            analyze         ,
+            analyze_tricks  ,
         }
     }
 }
+
+fn parse_card( s: &str ) -> Result<Cards, String> {
+    match s.parse::<Cards>() {
+        Ok( card ) if card.count() == 1 => Ok( card ),
+        _ => Err( format!( "\"{s}\" is not one card, e.g. 3c" )),
+    }
+}
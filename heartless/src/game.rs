@@ -6,9 +6,13 @@ use crate::*;
 use crate::cli::Config;
 
 use std::{
-    cell::Cell,
+    cell::RefCell,
     ffi::OsString,
-    io::Read,
+    fmt::{self, Display},
+    fs,
+    io::{self, Read},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
     process::{Command, Stdio},
     thread,
     time::Duration,
@@ -19,23 +23,31 @@ use std::io::Write;
 
 use wait_timeout::ChildExt;
 
-#[derive( Copy, Clone, Debug, PartialEq )]
+#[derive( Clone, Debug, PartialEq )]
 enum Input {
     Start,
     Discard( Cards ),
     Exit,
     Invalid,
     Pass( Cards ),
+    Save( PathBuf ),
+    Export( PathBuf ),
+    Undo,
 }
 
 /// The game rules of heartless which are checked.
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
 pub enum Rule {
+    /// The player can only act in their turn, e.g. passing before discarding, following the early hand.
+    OutOfTurn,
     /// The player can only pass the cards that they hold in hand.
     PassInHand,
     /// The players should pass excactly 3 cards, no more, no less.
     PassThreeCards,
     /// The player can only discard the cards that they hold in hand.
     DiscardInHand,
+    /// The player should discard exactly one card in each round.
+    DiscardOneCard,
     /// The early hand in round 1 should be Two of Clubs.
     TwoOfClubs,
     /// The late hands should follow the early hand's suit whenever possible.
@@ -44,81 +56,240 @@ pub enum Rule {
     Heartbreak,
     /// Not allowed to discard hearts in round 1, unless the player holds 13 hearts in hand which is almost impossible.
     HeartInFirstRound,
-    /// Not allowed to discard Queen of Spades in round 1.
+    /// Not allowed to discard Queen of Spades in round 1, nor other penalty cards than hearts of the rule pack.
     QueenInFirstRound,
 }
 
+/// The version of the files written by `Game::save_to()`.
+pub const SAVE_VERSION: u32 = 1;
+
+/// The file to save the game if the "save" command does not specify one.
+pub const DEFAULT_SAVE_FILE: &str = "heartless.save";
+
+/// The errors in loading a saved game.
+#[derive( Debug )]
+pub enum LoadError {
+    /// Failed to read the file.
+    Io( io::Error ),
+    /// The file is not of the kind expected, with its first line.
+    Header( String ),
+    /// The file is of another version of the format than the one supported, e.g. `SAVE_VERSION`.
+    Version{ found: u32, supported: u32 },
+    /// The line in the file is malformed, with its line number counting from 1.
+    Invalid{ line_no: usize, line: String },
+    /// The recorded action breaks the rule.
+    Illegal{ action: Action, rule: Rule },
+}
+
+impl Display for LoadError {
+    fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+        match self {
+            LoadError::Io( err ) => write!( f, "{err}" ),
+            LoadError::Header( header ) => write!( f, "unsupported file header: \"{header}\"" ),
+            LoadError::Version{ found, supported } => write!( f, "the file is of version {found}, but only version {supported} is supported" ),
+            LoadError::Invalid{ line_no, line } => write!( f, "malformed line {line_no}: \"{line}\"" ),
+            LoadError::Illegal{ action, rule } => write!( f, "{action:?} breaks the rule {rule:?}" ),
+        }
+    }
+}
+
+impl LoadError {
+    /// Checks the first line of a file, the `kind` of the file followed by the `version` of its format.
+    pub(crate) fn check_header( header: &str, kind: &str, version: u32 ) -> Result<(), LoadError> {
+        match header.strip_prefix( kind ).and_then( |rest| rest.strip_prefix( ' ' )).map( str::parse::<u32> ) {
+            Some( Ok( found )) if found == version => Ok(()),
+            Some( Ok( found ))                     => Err( LoadError::Version{ found, supported: version }),
+            _                                      => Err( LoadError::Header( header.to_owned() )),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from( err: io::Error ) -> Self { LoadError::Io( err )}
+}
+
 #[derive( Clone, Copy, PartialEq )]
 enum RoundStatus { Pending = 0, Complete = 1 }
 
-/// The splitters for splitting scores and cards in different rounds.
-pub const SPLITTER: [[&'static str; 13]; 2] = [
-    [
-        "  - - - - - - - - - - - -",
-        "-   - - - - - - - - - - -",
-        "- -   - - - - - - - - - -",
-        "- - -   - - - - - - - - -",
-        "- - - -   - - - - - - - -",
-        "- - - - -   - - - - - - -",
-        "- - - - - -   - - - - - -",
-        "- - - - - - -   - - - - -",
-        "- - - - - - - -   - - - -",
-        "- - - - - - - - -   - - -",
-        "- - - - - - - - - -   - -",
-        "- - - - - - - - - - -   -",
-        "- - - - - - - - - - - -  ",
-    ],
-    [
-        ". - - - - - - - - - - - -",
-        "- . - - - - - - - - - - -",
-        "- - . - - - - - - - - - -",
-        "- - - . - - - - - - - - -",
-        "- - - - . - - - - - - - -",
-        "- - - - - . - - - - - - -",
-        "- - - - - - . - - - - - -",
-        "- - - - - - - . - - - - -",
-        "- - - - - - - - . - - - -",
-        "- - - - - - - - - . - - -",
-        "- - - - - - - - - - . - -",
-        "- - - - - - - - - - - . -",
-        "- - - - - - - - - - - - .",
-    ],
-];
-
-/// The pointer type of functions that provide strategies for passing/discarding cards.
-pub type Strategy = fn(&Game,usize)->Cards;
-
-fn input_or_hint( game: &Game, _who: usize ) -> Cards {
-    game.pick_cards( ME ) // for hint only
+/// The rule of the game, in human readable text.
+pub const RULES: &str = r#"To be the player with the lowest score at the end of the game. When one player
+hits 100 score or higher, the game ends; and the player with the lowest score
+wins.
+
+At the end of each hand, players count the number of hearts they have taken as
+well as the queen of spades, if applicable. Hearts count as one point each and
+the queen counts 13 points.
+
+When a player takes all 13 hearts and the queen of spades in one hand, instead
+of losing 26 points, that player scores zero and each of his opponents score an
+additional 26 points.
+
+The player holding the 2 of clubs after the pass makes the opening lead. Each
+player must follow suit if possible. If a player is void of the suit led, a card
+of any other suit may be discarded. However, if a player has no clubs when the
+first trick is led, a heart or the queen of spades cannot be discarded. The
+highest card of the suit led wins a trick and the winner of that trick leads
+next. Hearts may not be led until a heart has been discarded.
+"#;
+
+/// The strategy of a player for passing/discarding cards, which picks them from the `PlayerView` of the player
+/// and may keep its own state between the calls, e.g. the cards counted so far or a random number generator.
+///
+/// Every call is given the `PlayerView`, which hides other players' hands.
+/// Any function or closure of `Fn(&PlayerView)->Cards` is a strategy that picks the cards to pass and discard
+/// alike and ignores the notifications.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+/// use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+///
+/// struct Counting {
+///     tricks : Arc<AtomicUsize>,
+/// }
+///
+/// impl Strategy for Counting {
+///     fn pass( &mut self, view: &PlayerView ) -> Cards {
+///         view.hand().pick_three_cards().dest
+///     }
+///     fn play( &mut self, view: &PlayerView ) -> Cards {
+///         view.hand().iter().find( |&card| view.break_the_rule( card ).is_none() ).unwrap_or_default()
+///     }
+///     fn on_trick_complete( &mut self, _view: &PlayerView, _trick: &Trick, _points: i32 ) {
+///         self.tricks.fetch_add( 1, Ordering::Relaxed );
+///     }
+/// }
+///
+/// let tricks = Arc::new( AtomicUsize::new( 0 ));
+/// let mut game = Game::with_config( cli::Config::from_args([ "heartless", "--automatic", "--impatient", "true",
+///     "--seed", "2024", "--count", "1", "--hands", "1" ]));
+/// game.strategies.set( LEFT, Counting{ tricks: tricks.clone() });
+/// game.main_loop();
+/// assert_eq!( tricks.load( Ordering::Relaxed ), 13 );
+/// ```
+pub trait Strategy {
+    /// Picks the three cards to pass.
+    fn pass( &mut self, view: &PlayerView ) -> Cards;
+    /// Picks the card to discard.
+    fn play( &mut self, view: &PlayerView ) -> Cards;
+    /// Notifies that a trick is complete, with the points taken by its winner.
+    fn on_trick_complete( &mut self, _view: &PlayerView, _trick: &Trick, _points: i32 ) {}
+    /// Notifies that the hand is scored, see `PlayerView::hand_score()` and `PlayerView::game_score()`.
+    fn on_hand_end( &mut self, _view: &PlayerView ) {}
+    /// Notifies that the last move was taken back, possibly with the tricks and the hand notified since then,
+    /// so the state kept by the strategy should be rebuilt from `view`, e.g. `PlayerView::tricks()`.
+    fn on_undo( &mut self, _view: &PlayerView ) {}
+}
+
+impl<F> Strategy for F where F: Fn(&PlayerView)->Cards {
+    fn pass( &mut self, view: &PlayerView ) -> Cards {
+        self( view )
+    }
+    fn play( &mut self, view: &PlayerView ) -> Cards {
+        self( view )
+    }
+}
+
+/// The built-in strategy, which plays by a few plain rules.
+#[derive( Clone, Copy )]
+struct Heuristic;
+
+impl Strategy for Heuristic {
+    fn pass( &mut self, view: &PlayerView ) -> Cards {
+        view.observed().pick_cards( view.who() )
+    }
+    fn play( &mut self, view: &PlayerView ) -> Cards {
+        view.observed().pick_cards( view.who() )
+    }
+}
+
+/// The Monte Carlo strategies selected by `BUILTIN_PIMC` and `BUILTIN_ISMCTS`, which pass cards as the built-in
+/// strategy does.
+///
+/// The samples are drawn from a generator seeded by `Config::seed` and what the player knows, so that the games
+/// are reproducible.
+#[derive( Clone, Copy )]
+enum MonteCarlo {
+    Pimc  { pimc  : Pimc  , seed: Option<u64> },
+    Ismcts{ ismcts: Ismcts, seed: Option<u64> },
+}
+
+impl Strategy for MonteCarlo {
+    fn pass( &mut self, view: &PlayerView ) -> Cards {
+        Heuristic.pass( view )
+    }
+    fn play( &mut self, view: &PlayerView ) -> Cards {
+        let ( MonteCarlo::Pimc{ seed, .. } | MonteCarlo::Ismcts{ seed, .. }) = *self;
+        let mut rng = match seed {
+            Some( seed ) => Rng::with_seed( seed ^ view.hand().0 ^ view.discarded().0.rotate_left( 32 )),
+            None         => Rng::new(),
+        };
+        match self {
+            MonteCarlo::Pimc  { pimc  , .. } => pimc  .pick_card( &view.observed(), view.who(), &mut rng ),
+            MonteCarlo::Ismcts{ ismcts, .. } => ismcts.pick_card( &view.observed(), view.who(), &mut rng ),
+        }
+    }
 }
 
+/// Selects the Perfect Information Monte Carlo strategy instead of a script, e.g. "--left builtin:pimc", see `Pimc`.
+pub const BUILTIN_PIMC: &str = "builtin:pimc";
+
+/// Selects the Information Set Monte Carlo Tree Search strategy instead of a script, e.g. "--left builtin:ismcts",
+/// see `Ismcts`.
+pub const BUILTIN_ISMCTS: &str = "builtin:ismcts";
+
 /// All players' strategies for passing/discarding cards.
 ///
 /// Note: `me` is invoked by `game` only in automatic mode, e.g. `game.config.me.is_some()`.
-#[derive( Debug )]
 pub struct Strategies {
-    of : [Strategy; 4],
+    of       : [RefCell<Box<dyn Strategy + Send>>; MAX_PLAYERS],
+    /// Whether the player runs its script instead, see `Game::pick_cards_by_script()`.
+    scripted : [bool; MAX_PLAYERS],
+}
+
+impl fmt::Debug for Strategies {
+    fn fmt( &self, f: &mut fmt::Formatter ) -> fmt::Result {
+        f.debug_struct( "Strategies" ).finish_non_exhaustive()
+    }
 }
 
 impl Strategies {
-    /// Decides to run scripts or using the built-in strategy, depending on `config`.
+    /// Decides to run scripts or using the built-in strategies, depending on `config`.
     pub fn from_config( config: &Config ) -> Self {
-        let me       = if config.me      .is_some() { Game::run_script_to_pick_cards } else { input_or_hint  };
-        let left     = if config.left    .is_some() { Game::run_script_to_pick_cards } else { Game::pick_cards };
-        let opposite = if config.opposite.is_some() { Game::run_script_to_pick_cards } else { Game::pick_cards };
-        let right    = if config.right   .is_some() { Game::run_script_to_pick_cards } else { Game::pick_cards };
-        Strategies{ of: [ me, left, opposite, right ]}
+        let strategy_of = |who: usize| -> Box<dyn Strategy + Send> {
+            match config.script_of( who ) {
+                Some( path ) if path.as_os_str() == BUILTIN_PIMC   => Box::new( MonteCarlo::Pimc{
+                    pimc: Pimc{ samples: config.pimc_samples, millis: config.pimc_millis, ..Pimc::default() },
+                    seed: config.seed,
+                }),
+                Some( path ) if path.as_os_str() == BUILTIN_ISMCTS => Box::new( MonteCarlo::Ismcts{
+                    ismcts: Ismcts{ iterations: config.ismcts_iters, millis: config.ismcts_millis },
+                    seed  : config.seed,
+                }),
+                _                                                  => Box::new( Heuristic ),
+            }
+        };
+        Strategies {
+            of       : std::array::from_fn( |who| RefCell::new( strategy_of( who ))),
+            scripted : std::array::from_fn( |who| config.script_of( who ).is_some_and( |path| !path.to_string_lossy().starts_with( "builtin:" ))),
+        }
+    }
+    /// Sets `strategy` for the player.
+    pub fn set( &mut self, who: usize, strategy: impl Strategy + Send + 'static ) {
+        self.of[ who ] = RefCell::new( Box::new( strategy ));
+        self.scripted[ who ] = false;
     }
     /// Sets `strategy` for all players.
-    pub fn set_for_all( &mut self, strategy: Strategy ) {
-        for whom in PLAYERS {
-            self.of[ whom ] = strategy;
+    pub fn set_for_all( &mut self, strategy: impl Strategy + Clone + Send + 'static ) {
+        for whom in ME..MAX_PLAYERS {
+            self.set( whom, strategy.clone() );
         }
     }
     /// Sets `strategy` for other players (not for me).
-    pub fn set_for_others( &mut self, strategy: Strategy ) {
-        for whom in OTHERS {
-            self.of[ whom ] = strategy;
+    pub fn set_for_others( &mut self, strategy: impl Strategy + Clone + Send + 'static ) {
+        for whom in LEFT..MAX_PLAYERS {
+            self.set( whom, strategy.clone() );
         }
     }
 }
@@ -129,7 +300,7 @@ impl Strategies {
 ///
 /// ```rust,no_run
 /// use heartless::*;
-/// fn your_great_ai( game: &Game, who: usize ) -> Cards { todo!() }
+/// fn your_great_ai( view: &PlayerView ) -> Cards { todo!() }
 ///
 /// let mut game = Game::default();
 /// game.strategies.set_for_others( your_great_ai );
@@ -137,19 +308,13 @@ impl Strategies {
 /// ```
 #[derive( Debug )]
 pub struct Game {
-        players         : [Player;4],
-        winner          : usize,
-        deal            : usize,
-    pub discarded       : Cards,
-    pub suit_mask       : Cards,
-    pub round           : usize,
-    pub under_the_gun   : usize,
-    pub round_winner    : usize,
-    pub high_card       : Cards,
+    pub state           : GameState,
         started         : bool,
         config          : Config,
-    pub hand            : Cell<Cards>,
+    pub rng             : Rng,
+        undos           : Vec<(GameState,Rng)>,
     pub strategies      : Strategies,
+        bots            : RefCell<[Option<Bot>;MAX_PLAYERS]>,
     pub altio           : Altio,
 }
 
@@ -161,6 +326,19 @@ impl Default for Game {
     }
 }
 
+impl Deref for Game {
+    type Target = GameState;
+    fn deref( &self ) -> &GameState {
+        &self.state
+    }
+}
+
+impl DerefMut for Game {
+    fn deref_mut( &mut self ) -> &mut GameState {
+        &mut self.state
+    }
+}
+
 impl Game {
     /// Constructs with given environment arguments.
     ///
@@ -173,25 +351,7 @@ impl Game {
         where I: IntoIterator<Item=T>
             , T: Into<OsString> + Clone
     {
-        let config = Config::from_args::<I,T>( iter );
-        let strategies = Strategies::from_config( &config );
-
-        Game {
-            players         : <[Player;4]>::default(),
-            winner          : NOBODY,
-            deal            : 0_usize,
-            discarded       : NO_CARD,
-            suit_mask       : NO_CARD,
-            round           : 0_usize,
-            under_the_gun   : NOBODY,
-            round_winner    : NOBODY,
-            high_card       : NO_CARD,
-            started         : false,
-            config          ,
-            hand            : Cell::new( NO_CARD ),
-            strategies      ,
-            altio           : Altio::default(),
-        }
+        Game::with_config( Config::from_args::<I,T>( iter ))
     }
     /// Constructs with given configurations.
     ///
@@ -201,50 +361,46 @@ impl Game {
     /// let config = heartless::cli::Config::from_args( std::env::args_os() );
     /// let mut game = heartless::Game::with_config( config );
     /// ```
+    #[allow( clippy::default_constructed_unit_structs )]
     pub fn with_config( config: Config ) -> Self {
         let strategies = Strategies::from_config( &config );
+        let mut state = GameState::with_ending( Ending {
+            target_score    : config.target_score,
+            hands           : config.hands,
+            tie_break       : config.tie_break,
+        });
+        state.rule_set = RuleSet::new( config.rule_pack, config.jack_of_diamonds );
+        state.rule_set.points_on_first = config.points_on_first;
+        state.rule_set.queen_breaks = config.queen_breaks;
+        state.rule_set.hearts_anytime = config.hearts_anytime;
+        state.rule_set.any_opening_suit = config.any_opening_suit;
+        state.moon_rule = config.moon_rule;
+        state.shoot_the_sun = config.shoot_the_sun;
+        state.pass_schedule = config.pass_schedule;
+        if !config.cancellation {
+            state.seats = config.players;
+        }
+        state.opening_card = config.opening_card;
+        state.partnership = config.partnership;
 
         Game {
-            players         : <[Player;4]>::default(),
-            winner          : NOBODY,
-            deal            : 0_usize,
-            discarded       : NO_CARD,
-            suit_mask       : NO_CARD,
-            round           : 0_usize,
-            under_the_gun   : NOBODY,
-            round_winner    : NOBODY,
-            high_card       : NO_CARD,
+            state           ,
             started         : false,
+            rng             : config.seed.map( Rng::with_seed ).unwrap_or_default(),
+            undos           : Vec::new(),
             config          ,
-            hand            : Cell::new( NO_CARD ),
             strategies      ,
+            bots            : RefCell::default(),
             altio           : Altio::default(),
         }
     }
-    /// Returns true if this player's hand may hold cards of the given suit,
-    /// otherwise returns false.
-    pub fn player_may_have( &self, who: usize, suit: u8 ) -> bool {
-        self.players[who].may_have( suit )
-    }
-    fn reset( &mut self ) {
-        self.players.iter_mut().for_each( |player| player.reset() );
-        self.winner         = NOBODY;
-        self.deal           = 0_usize;
-        self.discarded      = NO_CARD;
-        self.suit_mask      = NO_CARD;
-        self.round          = 0_usize;
-        self.under_the_gun  = NOBODY;
-        self.round_winner   = NOBODY;
-        self.high_card      = NO_CARD;
-        self.started        = false;
-    }
     fn start( &mut self ) {
         self.started = true;
     }
     fn input( &mut self ) -> Input {
         if self.config.automatic {
             if self.started {
-                let cards = self.pick_cards( ME );
+                let cards = self.pick_cards_for( ME ).dest;
                 if self.round == 0 {
                     Input::Pass( cards )
                 } else {
@@ -270,12 +426,21 @@ impl Game {
         let buffer = buffer.trim();
 
         let mut input = Input::Invalid;
-        let hand = self.players[ME].hand;
+        let hand = self.player(ME).hand;
         match buffer {
             "" => input = Input::Start,
             "exit" => input = Input::Exit,
+            "undo" => input = Input::Undo,
+            s if s == "save" || s.starts_with( "save " ) => {
+                let path = s[4..].trim();
+                input = Input::Save( PathBuf::from( if path.is_empty() { DEFAULT_SAVE_FILE } else { path }));
+            },
+            s if s == "export" || s.starts_with( "export " ) => {
+                let path = s[6..].trim();
+                input = Input::Export( PathBuf::from( if path.is_empty() { DEFAULT_REPLAY_FILE } else { path }));
+            },
             s if self.round > 0 => {
-                match Cards::parse_in_hand( &s, hand, self.suit_mask ) {
+                match Cards::parse_in_hand( s, hand, self.suit_mask ) {
                     Ok( card ) => {
                         if card.count() == 1 {
                             input = Input::Discard( card );
@@ -287,7 +452,7 @@ impl Game {
                 }
             },
             s if self.round == 0 => {
-                match Cards::parse_in_hand( &s, hand, self.suit_mask ) {
+                match Cards::parse_in_hand( s, hand, self.suit_mask ) {
                     Ok( cards ) => {
                         if cards.count() == 3 {
                             input = Input::Pass( cards );
@@ -302,293 +467,31 @@ impl Game {
         }
         input
     }
-    fn get_winner( &mut self ) -> usize {
-        let mut game_over = false;
-        let mut winner = NOBODY;
-        let mut min_score = 100;
-        for i in PLAYERS {
-            let s = self.players[i].game_score;
-            if s >= 100 {
-                game_over = true;
-            }
-            if min_score > s {
-                min_score = s;
-                winner = i;
-            }
-        }
-        if game_over {
-            self.winner = winner;
-            self.players[ winner ].awards += 1;
-            winner
-        } else {
-            NOBODY
-        }
-    }
-    fn deal( &mut self ) {
-        self.discarded = NO_CARD;
-        self.deal += 1;
-        self.round = 0;
-        let hands = Cards::deal();
-        for i in PLAYERS {
-            self.players[i].hand        = hands[i];
-            self.players[i].hand_score  = 0;
-            self.players[i].suit_bits   = BIT_CLUB | BIT_DIAMOND | BIT_SPADE | BIT_HEART;
-        }
+    fn text_flags( &self ) -> u32 {
+        let mut flags = 0;
+        if self.config.lowercase_rank { flags |= LOWERCASE_RANK }
+        if self.config.ascii_suit     { flags |= ASCII_SUIT     }
+        if self.config.no_shared_suit { flags |= NO_SHARED_SUIT }
+        if self.config.no_spaces      { flags |= NO_SPACES      }
+        flags
     }
-    fn needs_pass_cards( &self ) -> bool {
-        self.deal % 4 != 0
+    fn text_of( &self, cards: Cards ) -> String {
+        cards.text( self.text_flags() )
     }
-    fn pass_cards( &mut self, my_three_cards: Cards ) -> bool {
-        let mut transfers = <[Transfer; 4]>::default();
-        if let Some( transfered ) = self.players[ME].hand.transfer( my_three_cards ) {
-            transfers[ME] = transfered;
-            for i in OTHERS {
-                transfers[i] = self.pick_cards_for(i);
-            }
-            let offset = [0,3,1,2][ self.deal%4 ];
-            for i in PLAYERS {
-                let j = ( i + offset ) % 4;
-                self.players[i].hand = transfers[i].src + transfers[j].dest;
-                if i == ME {
-                    self.hint_received_cards( transfers[j].dest );
-                }
-            }
-            true
+    fn show_my_hand( &self ) {
+        if self.config.json {
+            echo!( self.out(), "{}", json::hand( ME, self.player(ME).hand ));
         } else {
-            self.hint_pass_cards_in_hands( my_three_cards );
-            false
+            echo!( self.out(), "= {}", self.text_of( self.player(ME).hand ));
         }
-
     }
-    pub fn break_the_rule( &self, who: usize, card: Cards ) -> Option<Rule> {
-        let hand = self.players[who].hand;
-
-        if self.round == 0 { // passing
-            if !hand.contains( card ) {
-                Some( Rule::PassInHand )
-            } else if card.count() != 3 {
-                Some( Rule::PassThreeCards )
+    fn show_winner( &self ) {
+        if self.winner() == ME {
+            if self.partner( ME ) != NOBODY {
+                echo!( self.out(), "The winners are me and the {} player!", self.name_of( self.partner( ME )));
             } else {
-                None
-            }
-        } else { // discarding
-            if !hand.contains( card ) {
-                return Some( Rule::DiscardInHand );
-            }
-
-            if hand.contains( TWO_OF_CLUBS ) && card != TWO_OF_CLUBS {
-                return Some( Rule::TwoOfClubs );
-            } else if {
-                self.suit_mask != NO_CARD &&
-                hand.contains_suit( self.suit_mask ) &&
-                !card.contains_suit( self.suit_mask )
-            } {
-                return Some( Rule::FollowSuit );
-            } else if card.is_heart() && !hand.are_all_of( HEARTS ) {
-                if self.under_the_gun == who {
-                    if !self.heart_broken() {
-                        return Some( Rule::Heartbreak );
-                    }
-                } else if self.round == 1 {
-                    return Some( Rule::HeartInFirstRound );
-                }
-            } else if card.is_queen_of_spade() {
-                if self.round == 1 {
-                    return Some( Rule::QueenInFirstRound );
-                }
-            }
-            None
-        }
-    }
-    fn heart_broken( &self ) -> bool {
-        self.discarded.contains_suit( HEARTS )
-    }
-    fn pick_card( &self, who: usize ) -> Transfer {
-        let hand = self.players[who].hand;
-
-        if self.under_the_gun == who { // early hand
-            if self.round == 1 {
-                return self.transfer_card( who, TWO_OF_CLUBS ).unwrap();
-            }
-
-            let mut suits_cnts = [
-                ( CLUB   , hand.count_suit( CLUBS )),
-                ( DIAMOND, hand.count_suit( DIAMONDS )),
-                ( SPADE  , if hand & SPADES >= QUEEN_OF_SPADES {14} else {1} ),
-                ( HEART  , if self.heart_broken() { hand.count_suit(HEARTS) } else {15} ),
-            ];
-            suits_cnts.sort_by_key( |suit_cnt| suit_cnt.1 ); // prefer discarding short suits than long suits
-
-            for (suit,_) in suits_cnts {
-                for rank in RANKS {
-                    let card = Cards::of( rank, suit );
-                    if let Some( transfered ) = self.transfer_card( who, card ) {
-                        return transfered;
-                    }
-                }
-            }
-        } else if hand & self.suit_mask != NO_CARD { // follow suit
-            let suit = self.suit();
-            let mut to_discard = Cards::of( ACE, suit );
-            let mut high_card_to_discard = NO_CARD;
-            for rank in RANKS.rev() {
-                let card = Cards::of( rank, suit );
-                if hand.contains( card ) {
-                    if card < self.high_card {
-                        if {
-                            self.suit_mask == SPADES &&
-                            hand.contains( QUEEN_OF_SPADES ) &&
-                            QUEEN_OF_SPADES < self.high_card
-                        } {
-                            return self.transfer_card( who, QUEEN_OF_SPADES ).unwrap();
-                        }
-                        return self.transfer_card( who, card ).unwrap();
-                    }
-                    if to_discard >= card {
-                        to_discard = card;
-                        if high_card_to_discard < card && suit != HEART && card != QUEEN_OF_SPADES {
-                            high_card_to_discard = card;
-                        }
-                    }
-                }
-            }
-            let mut late_hand_follows = false;
-            for i in 1..3 {
-                let i = (who+i) %4;
-                if i == self.under_the_gun {
-                    break;
-                }
-                if self.players[i].may_have( suit ) {
-                    late_hand_follows = true;
-                }
-            }
-            return self.transfer_card(
-                who,
-                if late_hand_follows || high_card_to_discard == NO_CARD { to_discard } else { high_card_to_discard }
-            ).unwrap();
-        } else { // don't follow suit
-            if hand.contains( QUEEN_OF_SPADES ) && self.round != 1 { // try to discard Queen of Spades
-                return self.transfer_card( who, QUEEN_OF_SPADES ).unwrap();
-            }
-            if hand.contains_suit( HEARTS ) && self.round != 1 { // try to discard hearts
-                for rank in RANKS.rev() {
-                    if let Some( transfered ) = self.transfer_card( who, Cards::of( rank, HEART )) {
-                        return transfered;
-                    }
-                }
-            }
-            for rank in RANKS.rev() { // try to discard high card
-                for suit in [SPADE, DIAMOND, CLUB] {
-                    let card = Cards::of( rank, suit );
-                    if card == QUEEN_OF_SPADES && self.round == 1 {
-                        continue;
-                    }
-                    if let Some( transfered ) = self.transfer_card( who, card ) {
-                        return transfered;
-                    }
-                }
-            }
-            for rank in RANKS.rev() { // all hearts
-                if let Some( transfered ) = self.transfer_card( who, Cards::of( rank, HEART )) {
-                    return transfered;
-                }
-            }
-        }
-        unreachable!();
-    }
-    fn transfer_card( &self, who: usize, card: Cards ) -> Option<Transfer> {
-        self.players[who].hand.transfer( card )
-    }
-    fn who_holds_two_of_clubs( &self ) -> usize {
-        for i in PLAYERS {
-            if self.players[i].hand.contains( TWO_OF_CLUBS ) {
-                return i;
-            }
-        }
-        NOBODY
-    }
-    fn discard( &mut self, who: usize, transfered: Transfer ) {
-        self.players[who].hand = transfered.src;
-        let discarding = transfered.dest;
-        self.players[who].discarding = discarding;
-        match self.suit_mask {
-            CLUBS    if !discarding.contains_suit( CLUBS    ) => self.players[who].suit_bits &= !BIT_CLUB   ,
-            DIAMONDS if !discarding.contains_suit( DIAMONDS ) => self.players[who].suit_bits &= !BIT_DIAMOND,
-            SPADES   if !discarding.contains_suit( SPADES   ) => self.players[who].suit_bits &= !BIT_SPADE  ,
-            HEARTS   if !discarding.contains_suit( HEARTS   ) => self.players[who].suit_bits &= !BIT_HEART  ,
-            _ => (),
-        }
-
-        self.discarded += discarding;
-        if self.suit_mask == NO_CARD {
-            self.suit_mask = transfered.dest.suit_mask();
-        }
-        let card = discarding & self.suit_mask;
-        if self.high_card < card {
-            self.high_card = card;
-            self.round_winner = who;
-        }
-    }
-    fn keep_score( &mut self ) {
-        let mut score = 0_u8;
-        for i in PLAYERS {
-            let discarding = self.players[i].discarding;
-            if discarding.is_heart() {
-                score += 1;
-            } else if discarding.is_queen_of_spade() {
-                score += 13;
-            }
-        }
-        if score != 0 {
-            self.players[ self.round_winner ].hand_score += score;
-            if self.players[ self.round_winner ].hand_score == 26 { // hit the moon
-                self.players[ self.round_winner ].hand_score = 0;
-                for i in PLAYERS {
-                    if i != self.round_winner {
-                        self.players[i].hand_score = 26;
-                    }
-                }
-            }
-        }
-        if self.round == 13 {
-            for i in PLAYERS {
-                self.players[i].game_score += self.players[i].hand_score;
+                echo!( self.out(), "The winner is me!" );
             }
-        }
-    }
-    fn next_round( &mut self ) -> bool {
-        self.round += 1;
-        for i in PLAYERS {
-            self.players[i].discarding = NO_CARD;
-        }
-        if self.round == 1 {
-            self.under_the_gun = self.who_holds_two_of_clubs();
-            self.suit_mask = CLUBS;
-        } else {
-            self.under_the_gun = self.round_winner;
-            self.suit_mask = NO_CARD;
-        };
-        self.round_winner = NOBODY;
-        self.high_card = NO_CARD;
-        self.round <= 13
-    }
-    fn suit( &self ) -> u8 {
-        self.suit_mask.mask_to_suit()
-    }
-    fn text_of( &self, cards: Cards ) -> String {
-        let mut mask = 0;
-        if self.config.lowercase_rank { mask |= LOWERCASE_RANK }
-        if self.config.ascii_suit     { mask |= ASCII_SUIT     }
-        if self.config.no_shared_suit { mask |= NO_SHARED_SUIT }
-        if self.config.no_spaces      { mask |= NO_SPACES      }
-        cards.text( mask )
-    }
-    fn show_my_hand( &self ) {
-        echo!( self.out(), "= {}", self.text_of( self.players[ME].hand ));
-    }
-    fn show_winner( &self ) {
-        if self.winner == ME {
-            echo!( self.out(), "The winner is me!" );
             echo!( self.err(),
 r#"
  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
@@ -615,9 +518,36 @@ r#"
 < :::::::::::::::::::::::::::::::::::::::::::::::::::::::::::: >
  ++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++"#
             );
+        } else if self.partner( self.winner() ) != NOBODY {
+            echo!( self.out(), "The winners are the {} and {} players!",
+                self.name_of( self.winner() ), self.name_of( self.partner( self.winner() )));
         } else {
-            echo!( self.out(), "The winner is the {} player!",
-                ["me", "left", "opposite", "right"][ self.winner ]);
+            echo!( self.out(), "The winner is the {} player!", self.name_of( self.winner() ));
+        }
+    }
+    fn hint_shot_the_moon( &self, who: usize, sun: bool, rule: MoonRule ) {
+        let team = self.team_of( who );
+        let shooter = match ( self.partnership, who ) {
+            ( Partnership::JointMoon, _ ) if team == ME => "We".to_owned(),
+            ( Partnership::JointMoon, _ ) => format!( "The {} and {} players", self.name_of( team ), self.name_of( self.partner( team ))),
+            ( _, ME ) => "I".to_owned(),
+            _ => format!( "The {} player", self.name_of( who )),
+        };
+        let ( what, points ) = if sun { ( "sun", 2 * self.rule_set.moon_points() ) } else { ( "moon", self.rule_set.moon_points() ) };
+        match rule {
+            MoonRule::SubtractFromSelf => echo!( self.out(), "{shooter} shot the {what}, -{points} points to the shooter!" ),
+            _                          => echo!( self.out(), "{shooter} shot the {what}, +{points} points to the others!" ),
+        }
+    }
+    /// The name of the player at the given seat as seen from me, e.g. "left".
+    fn name_of( &self, who: usize ) -> &'static str {
+        match who {
+            ME                                  => "me",
+            LEFT                                => "left",
+            _ if who == self.seats-1            => "right",
+            _ if who*2 == self.seats            => "opposite",
+            _ if who*2 < self.seats             => "left across",
+            _                                   => "right across",
         }
     }
     fn sleep_in_interactive_mode( &self ) {
@@ -629,109 +559,190 @@ r#"
             }
         }
     }
-    fn show_scores_and_discarding( &self, round_status: RoundStatus, who: usize ) {
+    fn show_scores_and_discarding( &self, round_status: RoundStatus, who: usize, trick: &Trick ) {
         if who != ME {
             self.sleep_in_interactive_mode();
         }
 
-        let p = &self.players;
+        let p = |who| self.player( who );
 
-        let mut out = self.out();
+        {
+            let mut out = self.out();
+
+            let mark = [" ", "."][ round_status as usize ];
+            let splitter = ( 1..=self.rounds() ).map( |round| if round == trick.round { mark } else { "-" }).collect::<Vec<_>>();
+            echo!( out, "{}", splitter.join( " " ));
+
+            if self.seats != 4 {
+                for who in self.players() {
+                    let gun = if who == trick.leader { ':' } else { ' ' };
+                    let win = if who == trick.winner { '.' } else { ' ' };
+                    echo!( out, "{:<12} {:>7}  {gun}{}{win}", self.name_of( who ),
+                        format!( "{}/{}", p(who).hand_score, p(who).game_score ), self.text_of( trick.cards[ who ]));
+                }
+            } else {
+                echo!( out, "       {:>7}",
+                    format!( "{}/{}", p(OPPOSITE).hand_score, p(OPPOSITE).game_score ));
+                echo!( out, "{:>7}       {:>7}",
+                    format!( "{}/{}", p(LEFT) .hand_score, p(LEFT) .game_score ),
+                    format!( "{}/{}", p(RIGHT).hand_score, p(RIGHT).game_score )
+                );
+                echo!( out, "       {:>7}",
+                    format!( "{}/{}", p(ME).hand_score, p(ME).game_score ));
+                echo!( out, "" );
+
+                let mut gun = [' ', ' ', ' ', ' '];
+                gun[ trick.leader ] = ':';
+                let [gm, gl, go, gr] = gun;
 
-        echo!( out, "{}", SPLITTER[ round_status as usize ][ self.round-1 ]);
-        echo!( out, "       {:>7}",
-            format!( "{}/{}", p[OPPOSITE].hand_score, p[OPPOSITE].game_score ));
-        echo!( out, "{:>7}       {:>7}",
-            format!( "{}/{}", p[LEFT] .hand_score, p[LEFT] .game_score ),
-            format!( "{}/{}", p[RIGHT].hand_score, p[RIGHT].game_score )
-        );
-        echo!( out, "       {:>7}",
-            format!( "{}/{}", p[ME].hand_score, p[ME].game_score ));
-        echo!( out, "" );
-
-        let mut gun = [' ', ' ', ' ', ' '];
-        gun[ self.under_the_gun ] = ':';
-        let [gm, gl, go, gr] = gun;
-
-        let mut win = [' ', ' ', ' ', ' '];
-        win[ self.round_winner ] = '.';
-        let [wm, wl, wo, wr] = win;
-
-        let co = p[ OPPOSITE ].discarding;
-        let cl = p[ LEFT     ].discarding;
-        let cr = p[ RIGHT    ].discarding;
-        let cm = p[ ME       ].discarding;
-
-        echo!( out, "          {go}{}{wo}"   , self.text_of(co) );
-        echo!( -n,
-               out, "   {gl}{}{wl}"          , self.text_of(cl) );
-        if cl == NO_CARD {
-            echo!( -n, out, "  " );
-        }
-        echo!( out,              "          {gr}{}{wr}"
-                                             , self.text_of(cr) );
-        echo!( out, "          {gm}{}{wm}"   , self.text_of(cm) );
-
-        drop( out );
+                let mut win = [' ', ' ', ' ', ' '];
+                win[ trick.winner ] = '.';
+                let [wm, wl, wo, wr] = win;
+
+                let co = trick.cards[ OPPOSITE ];
+                let cl = trick.cards[ LEFT     ];
+                let cr = trick.cards[ RIGHT    ];
+                let cm = trick.cards[ ME       ];
+
+                echo!( out, "          {go}{}{wo}"   , self.text_of(co) );
+                echo!( -n,
+                       out, "   {gl}{}{wl}"          , self.text_of(cl) );
+                if cl == NO_CARD {
+                    echo!( -n, out, "  " );
+                }
+                echo!( out,              "          {gr}{}{wr}"
+                                                     , self.text_of(cr) );
+                echo!( out, "          {gm}{}{wm}"   , self.text_of(cm) );
+            }
+        }
 
         match round_status {
-            RoundStatus::Pending => if (who+1) % 4 != ME {
+            RoundStatus::Pending => if (who+1) % self.seats != ME {
                 self.show_my_hand();
                 echo!( self.out(), "discarding..." );
             }
-            RoundStatus::Complete => if self.round_winner != ME && self.round != 13 {
+            RoundStatus::Complete => if trick.winner != ME && trick.round != self.rounds() {
                 self.show_my_hand();
                 echo!( self.out(), "discarding..." );
                 self.sleep_in_interactive_mode();
             }
         }
     }
+    /// Shows the events resulting from applying actions.
+    fn show_events( &self, events: Vec<Event> ) {
+        self.notify_strategies( &events );
+        self.notify_bots( &events );
+        if self.config.json {
+            for event in events {
+                echo!( self.out(), "{}", event.to_json( ME, self.seats ));
+                if let Event::Received{ who: ME, .. } = event {
+                    self.show_my_hand();
+                }
+            }
+            return;
+        }
+        let mut events = events.into_iter().peekable();
+        let mut received = NO_CARD;
+        while let Some( event ) = events.next() {
+            match event {
+                Event::Received{ who: ME, from:_, cards } => {
+                    received += cards;
+                    if !matches!( events.peek(), Some( Event::Received{ who: ME, .. })) {
+                        self.hint_received_cards( received );
+                    }
+                },
+                Event::Discarded{ who, card:_ } if !matches!( events.peek(), Some( Event::TrickWon{..} )) =>
+                    self.show_scores_and_discarding( RoundStatus::Pending, who, &self.trick() ),
+                Event::TrickWon{ trick, points:_ } =>
+                    self.show_scores_and_discarding( RoundStatus::Complete, (trick.leader+self.seats-1) % self.seats, &trick ),
+                Event::ShotTheMoon{ who, sun, rule } => self.hint_shot_the_moon( who, sun, rule ),
+                Event::GameOver{ winner:_ } => self.show_winner(),
+                _ => (),
+            }
+        }
+    }
+    /// Runs the script of the player to pick the cards, as `Config::script_mode` tells.
+    fn pick_cards_by_script( &self, who: usize ) -> Cards {
+        match self.config.script_mode {
+            ScriptMode::PerCall   => self.run_script_to_pick_cards( who ),
+            ScriptMode::JsonLines => self.ask_bot_to_pick_cards( who ),
+        }
+    }
     fn run_script_to_pick_cards( &self, who: usize ) -> Cards {
-        let script_path = match who {
-            ME       => if let Some( path ) = self.config.me      .as_ref() { path.clone() } else { return NO_CARD },
-            LEFT     => if let Some( path ) = self.config.left    .as_ref() { path.clone() } else { return NO_CARD },
-            OPPOSITE => if let Some( path ) = self.config.opposite.as_ref() { path.clone() } else { return NO_CARD },
-            RIGHT    => if let Some( path ) = self.config.right   .as_ref() { path.clone() } else { return NO_CARD },
-            _ => return NO_CARD,
+        let script_path = match self.config.script_of( who ) {
+            Some( path ) => path.clone(),
+            None         => return NO_CARD,
         };
-        let hand                = self.players[who].hand;
-        let discarded           = self.discarded;
-        let suit_to_follow      = ["club", "diamond", "spade", "heart", ""][ (self.suit()>>4) as usize ];
-        let round               = self.round;
-        let under_the_gun       = self.under_the_gun;
-        let round_winner        = self.round_winner;
-        let high_card           = self.high_card;
-
-        let my_discarding       = self.players[ME].discarding;
-        let my_hand_score       = self.players[ME].hand_score;
-        let my_game_score       = self.players[ME].game_score;
-        let my_suit_chars       = self.players[ME].suit_chars();
-
-        let left_discarding     = self.players[LEFT].discarding;
-        let left_hand_score     = self.players[LEFT].hand_score;
-        let left_game_score     = self.players[LEFT].game_score;
-        let left_suit_chars     = self.players[LEFT].suit_chars();
-
-        let opposite_discarding = self.players[OPPOSITE].discarding;
-        let opposite_hand_score = self.players[OPPOSITE].hand_score;
-        let opposite_game_score = self.players[OPPOSITE].game_score;
-        let opposite_suit_chars = self.players[OPPOSITE].suit_chars();
-
-        let right_discarding    = self.players[RIGHT].discarding;
-        let right_hand_score    = self.players[RIGHT].hand_score;
-        let right_game_score    = self.players[RIGHT].game_score;
-        let right_suit_chars    = self.players[RIGHT].suit_chars();
+        let view                = self.view_of( who );
+        let hand                = view.hand();
+        let legal               = view.legal();
+        let discarded           = view.discarded();
+        let suit_to_follow      = ["club", "diamond", "spade", "heart", ""][ (view.suit()>>4) as usize ];
+        let round               = view.round();
+        let trick               = view.trick();
+        let under_the_gun       = trick.leader;
+        let round_winner        = trick.winner;
+        let high_card           = view.high_card();
+        let jack_of_diamonds    = view.points_of( JACK_OF_DIAMONDS );
+        let partner             = view.partner( who );
+        let rule_pack           = clap::ValueEnum::to_possible_value( &view.rule_set().pack ).map( |value| value.get_name().to_owned() ).unwrap_or_default();
+        let pass_to             = view.pass_receivers().iter().map( usize::to_string ).collect::<Vec<_>>().join( "," );
+        let passed              = view.passed();
+        let received            = view.received();
+        let tricks              = view.tricks().iter().map( |( trick, _ )| {
+            let plays = ( 0..view.seats() ).map( |nth| trick.cards[ ( trick.leader + nth ) % view.seats() ].to_string() ).collect::<String>();
+            format!( "{}:{plays}:{}", trick.leader, trick.winner )
+        }).collect::<Vec<_>>().join( "," );
+        let seats               = view.seats();
+        let by_seat             = |of: &dyn Fn( usize ) -> String| ( 0..seats ).map( of ).collect::<Vec<_>>().join( "," );
+        let discardings         = by_seat( &|who| trick.cards[ who ].to_string() );
+        let hand_scores         = by_seat( &|who| view.hand_score( who ).to_string() );
+        let game_scores         = by_seat( &|who| view.game_score( who ).to_string() );
+        let suit_chars          = by_seat( &|who| view.suit_chars( who ).to_owned() );
+
+        let my_discarding       = trick.cards[ ME ];
+        let my_hand_score       = view.hand_score( ME );
+        let my_game_score       = view.game_score( ME );
+        let my_suit_chars       = view.suit_chars( ME );
+
+        let left_discarding     = trick.cards[ LEFT ];
+        let left_hand_score     = view.hand_score( LEFT );
+        let left_game_score     = view.game_score( LEFT );
+        let left_suit_chars     = view.suit_chars( LEFT );
+
+        let opposite_discarding = trick.cards[ OPPOSITE ];
+        let opposite_hand_score = view.hand_score( OPPOSITE );
+        let opposite_game_score = view.game_score( OPPOSITE );
+        let opposite_suit_chars = view.suit_chars( OPPOSITE );
+
+        let right_discarding    = trick.cards[ RIGHT ];
+        let right_hand_score    = view.hand_score( RIGHT );
+        let right_game_score    = view.game_score( RIGHT );
+        let right_suit_chars    = view.suit_chars( RIGHT );
 
         let mut cmd = Command::new( script_path );
         cmd.args([
                 &format!( "--who={who}" ),
                 &format!( "--hand={hand:-?}" ),
+                &format!( "--legal={legal:-?}" ),
                 &format!( "--discarded={discarded:-?}" ),
                 &format!( "--suit_to_follow={suit_to_follow}" ),
                 &format!( "--round={round}" ),
                 &format!( "--under_the_gun={under_the_gun}" ),
                 &format!( "--round_winner={round_winner}" ),
                 &format!( "--high_card={high_card}" ),
+                &format!( "--jack_of_diamonds={jack_of_diamonds}" ),
+                &format!( "--partner={partner}" ),
+                &format!( "--rule_pack={rule_pack}" ),
+                &format!( "--pass_to={pass_to}" ),
+                &format!( "--passed={passed:-?}" ),
+                &format!( "--received={received:-?}" ),
+                &format!( "--tricks={tricks}" ),
+                &format!( "--seats={seats}" ),
+                &format!( "--discardings={discardings}" ),
+                &format!( "--hand_scores={hand_scores}" ),
+                &format!( "--game_scores={game_scores}" ),
+                &format!( "--suit_chars={suit_chars}" ),
                 &format!( "--my_discarding={my_discarding}" ),
                 &format!( "--my_hand_score={my_hand_score}" ),
                 &format!( "--my_game_score={my_game_score}" ),
@@ -761,11 +772,11 @@ r#"
                 let timeout = Duration::from_millis( timeout );
                 match spawned.wait_timeout( timeout ) {
                     Ok(Some(_)) => {
-                        spawned.stderr.and_then( |mut stderr| {
+                        if let Some( mut stderr ) = spawned.stderr {
                             let mut buf = String::new();
                             stderr.read_to_string( &mut buf ).ok();
-                            Some( echo!( self.err(), "{buf}" ))
-                        });
+                            echo!( self.err(), "{buf}" );
+                        }
                         spawned.stdout.map( |mut stdout| {
                             let mut buf = String::new();
                             stdout.read_to_string( &mut buf ).ok();
@@ -778,7 +789,7 @@ r#"
                         return NO_CARD;
                     },
                     Err( e ) => {
-                        echo!( self.err(), "{}", format!( "{e:#?}" ).replace( "\n", "\r" ));
+                        echo!( self.err(), "{}", format!( "{e:#?}" ).replace( '\n', "\r" ));
                         return NO_CARD;
                     },
                 }
@@ -796,8 +807,11 @@ r#"
                 },
             }
         };
+        self.parse_script_output( who, &output )
+    }
+    fn parse_script_output( &self, who: usize, output: &str ) -> Cards {
         let output = output.trim();
-        match Cards::parse_in_hand( output, hand, self.suit_mask ) {
+        match Cards::parse_in_hand( output, self.player(who).hand, self.suit_mask ) {
             Ok( cards ) => match self.break_the_rule( who, cards ) {
                 Some( rule ) => self.hint_break_the_rule( cards, rule ),
                 None => return cards,
@@ -806,47 +820,213 @@ r#"
         }
         NO_CARD
     }
-}
-
-impl Game {
-    fn hint_rules( &self ) {
-        echo!( self.err(), r#"Welcome to play heartless!
-
-To be the player with the lowest score at the end of the game. When one player
-hits 100 score or higher, the game ends; and the player with the lowest score
-wins.
-
-At the end of each hand, players count the number of hearts they have taken as
-well as the queen of spades, if applicable. Hearts count as one point each and
-the queen counts 13 points.
-
-When a player takes all 13 hearts and the queen of spades in one hand, instead
-of losing 26 points, that player scores zero and each of his opponents score an
-additional 26 points.
+    /// Starts the scripts run in `ScriptMode::JsonLines` when a game starts, sends them the events
+    /// in their own views, and stops them when the game is over.
+    fn notify_bots( &self, events: &[Event] ) {
+        if self.config.script_mode != ScriptMode::JsonLines || self.config.replay.is_some() {
+            return;
+        }
+        for event in events {
+            if *event == Event::GameStarted {
+                for who in self.players() {
+                    self.bots.borrow_mut()[ who ] = self.spawn_bot( who );
+                }
+            }
+            for who in self.players() {
+                let mut bots = self.bots.borrow_mut();
+                if let Some( bot ) = bots[ who ].as_mut() {
+                    if let Err( err ) = bot.send( &event.to_json( who, self.seats )) {
+                        echo!( self.err(), "Failed to send the event to the script of {}: {err}", self.name_of( who ));
+                        bots[ who ] = None;
+                    }
+                }
+            }
+            if let Event::GameOver{ .. } = event {
+                *self.bots.borrow_mut() = Default::default();
+            }
+        }
+    }
+    /// Notifies every player's strategy of the completed tricks and the scored hands, see `Strategy`.
+    fn notify_strategies( &self, events: &[Event] ) {
+        if self.config.replay.is_some() {
+            return;
+        }
+        for event in events {
+            for who in self.players() {
+                match *event {
+                    Event::TrickWon{ trick, points } => self.strategies.of[ who ].borrow_mut().on_trick_complete( &self.view_of( who ), &trick, points ),
+                    Event::HandScored{ .. }          => self.strategies.of[ who ].borrow_mut().on_hand_end( &self.view_of( who )),
+                    _                                => (),
+                }
+            }
+        }
+    }
+    /// Resyncs every player's strategy and script run in `ScriptMode::JsonLines` after `Game::undo()`,
+    /// since the events notified before may have been taken back.
+    fn notify_undo( &self ) {
+        if self.config.replay.is_some() {
+            return;
+        }
+        for who in self.players() {
+            self.strategies.of[ who ].borrow_mut().on_undo( &self.view_of( who ));
+            let mut bots = self.bots.borrow_mut();
+            if let Some( bot ) = bots[ who ].as_mut() {
+                if let Err( err ) = bot.send( &json::undone( &self.view_of( who ))) {
+                    echo!( self.err(), "Failed to send the event to the script of {}: {err}", self.name_of( who ));
+                    bots[ who ] = None;
+                }
+            }
+        }
+    }
+    fn spawn_bot( &self, who: usize ) -> Option<Bot> {
+        let path = self.config.script_of( who ).filter( |path| !path.to_string_lossy().starts_with( "builtin:" ))?;
+        Bot::spawn( path, who )
+            .map_err( |err| echo!( self.err(), "Failed to start {}: {err}", path.display() ))
+            .ok()
+    }
+    /// Asks the script run in `ScriptMode::JsonLines` for the cards to pass or discard, starting it if not yet.
+    ///
+    /// The script is stopped if it fails to answer in time, and started again on the next request.
+    fn ask_bot_to_pick_cards( &self, who: usize ) -> Cards {
+        let mut bots = self.bots.borrow_mut();
+        if bots[ who ].is_none() {
+            bots[ who ] = self.spawn_bot( who );
+        }
+        let Some( bot ) = bots[ who ].as_mut() else { return NO_CARD };
+        let view = self.view_of( who );
+        let request = if view.round() == 0 { json::request_pass( &view )} else { json::request_play( &view )};
+        let reply = bot.send( &request ).ok().and_then( |_| bot.recv( self.config.timeout.map( Duration::from_millis )));
+        drop( bots );
+        match reply {
+            Some( line ) => match json::string_field( &line, "cards" ) {
+                Some( cards ) => self.parse_script_output( who, &cards ),
+                None          => self.parse_script_output( who, &line ),
+            },
+            None => {
+                echo!( self.err(), "The script of {} exited or ran out of time.", self.name_of( who ));
+                self.bots.borrow_mut()[ who ] = None;
+                NO_CARD
+            },
+        }
+    }
+}
 
-The player holding the 2 of clubs after the pass makes the opening lead. Each
-player must follow suit if possible. If a player is void of the suit led, a card
-of any other suit may be discarded. However, if a player has no clubs when the
-first trick is led, a heart or the queen of spades cannot be discarded. The
-highest card of the suit led wins a trick and the winner of that trick leads
-next. Hearts may not be led until a heart has been discarded.
-"#
-        );
+impl Game {
+    fn hint_rules( &self ) {
+        if !self.config.json {
+            echo!( self.err(), "Welcome to play heartless!\n\n{RULES}" );
+            for rule in self.house_rules().lines() {
+                echo!( self.err(), "{rule}\n" );
+            }
+        }
+    }
+    /// The rules of this game that differ from `RULES`, one line each.
+    pub fn house_rules( &self ) -> String {
+        let mut rules = Vec::new();
+        if self.config.cancellation {
+            rules.push( format!( "In this game of Cancellation Hearts, {} players share two decks without passing, and identical cards in a trick cancel each other.",
+                self.config.players ));
+        }
+        if self.seats != 4 {
+            rules.push( format!( "In this game, {} players are dealt {} cards each, without {}.", self.seats, self.rounds(),
+                self.text_of( CLUBS + DIAMONDS + SPADES + HEARTS - self.deck() )));
+        }
+        let lowest_club = ( self.deck() & CLUBS ).iter().next().unwrap_or_default();
+        if self.opening_card != lowest_club {
+            rules.push( format!( "In this game, {} leads the first trick instead of {}.", self.text_of( self.opening_card ), self.text_of( lowest_club )));
+        }
+        if let Some( hands ) = self.ending.hands {
+            rules.push( format!( "In this game, {hands} hands will be played instead." ));
+        } else if self.ending.target_score != 100 {
+            rules.push( format!( "In this game, the target score is {} instead of 100.", self.ending.target_score ));
+        }
+        let ( pack, points ) = match self.rule_set.pack {
+            RulePack::Standard   => ( None                , None ),
+            RulePack::BlackMaria => ( Some( "Black Maria" ), Some( "the Ace of Spades counts 7 points and the King of Spades 10 points" )),
+            RulePack::Omnibus    => ( Some( "Omnibus"     ), None ),
+            RulePack::SpotHearts => ( Some( "Spot Hearts" ), Some( "hearts count their face value, e.g. 11 for the Jack and 14 for the Ace" )),
+            RulePack::PinkLady   => ( Some( "Pink Lady"   ), Some( "the Queen of Hearts counts 13 points" )),
+        };
+        if let Some( pack ) = pack {
+            rules.push( format!( "Rule pack: {pack}." ));
+        }
+        if let Some( points ) = points {
+            rules.push( format!( "In this game, {points}." ));
+        }
+        if self.rule_set.jack_of_diamonds {
+            rules.push( "In this game, the Jack of Diamonds counts -10 points.".to_owned() );
+        }
+        if self.rule_set.points_on_first {
+            rules.push( "In this game, hearts and the Queen of Spades may be discarded in the first trick.".to_owned() );
+        }
+        if self.rule_set.hearts_anytime {
+            rules.push( "In this game, hearts may be led at any time.".to_owned() );
+        } else if self.rule_set.queen_breaks {
+            rules.push( "In this game, discarding the Queen of Spades breaks hearts.".to_owned() );
+        }
+        if self.rule_set.any_opening_suit {
+            rules.push( format!( "In this game, the first trick may be led by any card of the suit of {}.", self.text_of( self.opening_card )));
+        }
+        let moon_points = self.rule_set.moon_points();
+        match self.moon_rule {
+            MoonRule::AddToOthers      => if moon_points != 26 {
+                rules.push( format!( "In this game, shooting the moon adds {moon_points} points to the others." ));
+            },
+            MoonRule::SubtractFromSelf => rules.push( format!( "In this game, shooting the moon subtracts {moon_points} points from the shooter instead." )),
+            MoonRule::ShooterChooses   => rules.push( format!( "In this game, shooting the moon subtracts {moon_points} points from the shooter instead if adding to others loses the game." )),
+        }
+        match self.pass_schedule {
+            PassSchedule::LeftRightAcrossHold => (),
+            PassSchedule::LeftRight           => rules.push( "In this game, players pass cards to the left and the right in turn.".to_owned() ),
+            PassSchedule::AlwaysLeft          => rules.push( "In this game, players always pass cards to the left.".to_owned() ),
+            PassSchedule::NoPassing           => rules.push( "In this game, players do not pass cards.".to_owned() ),
+            PassSchedule::Scatter             => rules.push( "In this game, players pass the lowest card to the left, the middle across and the highest to the right.".to_owned() ),
+        }
+        match self.partnership {
+            Partnership::Off       => (),
+            Partnership::SoloMoon  => rules.push( "In this game, partners sitting opposite pool their scores, and shoot the moon alone.".to_owned() ),
+            Partnership::JointMoon => rules.push( "In this game, partners sitting opposite pool their scores, and shoot the moon together.".to_owned() ),
+        }
+        if self.shoot_the_sun {
+            rules.push( format!( "In this game, taking all the tricks shoots the sun, which scores {} points instead of {moon_points}.", 2 * moon_points ));
+        }
+        rules.iter().map( |rule| format!( "{rule}\n" )).collect()
+    }
+    fn hint_invalid_input( &self, message: &str ) {
+        if self.config.json {
+            echo!( self.out(), "{}", json::message( "invalid_input", message ));
+        } else {
+            echo!( self.err(), "{message}" );
+        }
     }
-    fn hint_break_the_rule( &self, cards: Cards, rule: Rule ) {
+    /// Prints the hint on which rule the cards break.
+    pub fn hint_break_the_rule( &self, cards: Cards, rule: Rule ) {
+        if self.config.json {
+            echo!( self.out(), "{}", json::rule_violation( rule, cards ));
+            return;
+        }
         match rule {
+            Rule::OutOfTurn         => self.hint_out_of_turn(),
             Rule::PassInHand        => self.hint_pass_cards_in_hands( cards ),
             Rule::PassThreeCards    => self.hint_pass_three_cards( cards ),
             Rule::DiscardInHand     => self.hint_discard_card_in_hand( cards ),
+            Rule::DiscardOneCard    => self.hint_discard_one( cards ),
             Rule::TwoOfClubs        => self.hint_two_of_clubs(),
             Rule::FollowSuit        => self.hint_follow_suit(),
             Rule::Heartbreak        => self.hint_no_heartbreak(),
             Rule::HeartInFirstRound => self.hint_heart_in_first_round(),
-            Rule::QueenInFirstRound => self.hint_queen_in_first_round(),
+            Rule::QueenInFirstRound => self.hint_queen_in_first_round( cards ),
         }
     }
     fn hint_deal( &self ) {
-        echo!( self.out(), "Press enter to start..." );
+        if self.config.json {
+            echo!( self.out(), "{}", json::prompt( "start", NO_CARD ));
+        } else {
+            echo!( self.out(), "Press enter to start..." );
+        }
+    }
+    fn hint_out_of_turn( &self ) {
+        echo!( self.err(), "Not your turn." );
     }
     fn hint_discard_card_in_hand( &self, card: Cards ) {
         echo!( self.err(), "Discarding card is not in hand: {}", self.text_of(card) );
@@ -858,7 +1038,11 @@ next. Hearts may not be led until a heart has been discarded.
         echo!( self.err(), "These are not three cards: {}", self.text_of(cards) );
     }
     fn hint_two_of_clubs( &self ) {
-        echo!( self.err(), "Two of clubs should be dropped first!" );
+        if self.opening_card == TWO_OF_CLUBS {
+            echo!( self.err(), "Two of clubs should be dropped first!" );
+        } else {
+            echo!( self.err(), "{} should be dropped first!", self.text_of( self.opening_card ));
+        }
     }
     fn hint_follow_suit( &self ) {
         echo!( self.err(), "You must follow the suit!" );
@@ -869,64 +1053,602 @@ next. Hearts may not be led until a heart has been discarded.
     fn hint_heart_in_first_round( &self ) {
         echo!( self.err(), "Heart not allowed in first round." );
     }
-    fn hint_queen_in_first_round( &self ) {
-        echo!( self.err(), "Queen of Spades not allowed in first round." );
+    fn hint_queen_in_first_round( &self, cards: Cards ) {
+        if cards == QUEEN_OF_SPADES {
+            echo!( self.err(), "Queen of Spades not allowed in first round." );
+        } else {
+            echo!( self.err(), "{} not allowed in first round.", self.text_of( cards ));
+        }
     }
     fn hint_ambiguous_cards( &self, cards: Cards ) {
-        echo!( self.err(), "Which card to discard? {}", self.text_of(cards) );
+        self.hint_invalid_input( &format!( "Which card to discard? {}", self.text_of(cards) ));
     }
     fn hint_discard_one( &self, cards: Cards ) {
-        echo!( self.err(), "Not allowed to discard multiple cards in one round: {}", self.text_of(cards) );
+        self.hint_invalid_input( &format!( "Not allowed to discard multiple cards in one round: {}", self.text_of(cards) ));
     }
     fn hint_pass_three( &self, cards: Cards ) {
-        echo!( self.err(), "These are not 3 cards to pass: {}", self.text_of(cards) );
+        self.hint_invalid_input( &format!( "These are not 3 cards to pass: {}", self.text_of(cards) ));
     }
     fn hint_valid_chars_for_cards( &self, invalid: char, input: &str ) {
-        echo!( self.err(), r#"This string \"{input}\" contains an invalid char '{invalid}'.
+        self.hint_invalid_input( &format!( r#"This string \"{input}\" contains an invalid char '{invalid}'.
 Please use 2,3,4,5,6,7,8,9,0,J,Q,K,A for ranks and c,d,s,h for suits.
 Ranks followed by a suit representing cards with these ranks and in the suit.
 For example, Qs means Queen of Spades.
-Another example: 0Jh means Ten of Hearts and Jack of Hearts.\n"# );
+Another example: 0Jh means Ten of Hearts and Jack of Hearts.\n"# ));
     }
     fn hint_no_card_parsed( &self, input: &str ) {
-        echo!( self.err(), "This string does not represent any card: \"{input}\"" );
+        self.hint_invalid_input( &format!( "This string does not represent any card: \"{input}\"" ));
     }
     fn hint_pass( &self ) {
-        let receiver = ["nobody", "left", "right", "opposite"][ self.deal%4 ];
+        let receiver = match self.pass_receivers( ME )[..] {
+            [ receiver ] => self.name_of( receiver ),
+            _            => "left, opposite and right in order",
+        };
         let three_cards = self.pick_cards_for(ME).dest;
-        echo!( self.out(), "Pass 3 cards to {receiver}, e.g. {three_cards}" );
+        if self.config.json {
+            echo!( self.out(), "{}", json::prompt( "pass", three_cards ));
+        } else {
+            echo!( self.out(), "Pass 3 cards to {receiver}, e.g. {three_cards}" );
+        }
     }
     fn hint_discard( &self ) {
         let card = self.pick_cards_for(ME).dest;
-        echo!( self.out(), "Discard 1 card, e.g. {card}" );
+        if self.config.json {
+            echo!( self.out(), "{}", json::prompt( "discard", card ));
+        } else {
+            echo!( self.out(), "Discard 1 card, e.g. {card}" );
+        }
     }
     fn hint_no_pass_discard( &self ) {
         let card = self.pick_cards_for(ME).dest;
-        echo!( self.out(), "Do not need to pass cards. Discard 1 card, e.g. {card}" );
+        if self.config.json {
+            echo!( self.out(), "{}", json::prompt( "discard", card ));
+        } else {
+            echo!( self.out(), "Do not need to pass cards. Discard 1 card, e.g. {card}" );
+        }
+    }
+    fn hint_loaded( &self, path: &Path ) {
+        if self.config.json {
+            echo!( self.out(), "{}", json::message( "loaded", &path.display().to_string() ));
+        } else {
+            echo!( self.out(), "Loaded {}", path.display() );
+        }
+    }
+    fn hint_load_failed( &self, path: &Path, err: LoadError ) {
+        let message = format!( "Failed to load {}: {err}", path.display() );
+        if self.config.json {
+            echo!( self.out(), "{}", json::message( "load_failed", &message ));
+        } else {
+            echo!( self.err(), "{message}" );
+        }
+    }
+    fn save_and_hint( &self, path: &Path ) {
+        match self.save_to( path ) {
+            Ok(()) => if self.config.json {
+                echo!( self.out(), "{}", json::message( "saved", &path.display().to_string() ));
+            } else {
+                echo!( self.out(), "Saved to {}", path.display() );
+            },
+            Err( err ) => {
+                let message = format!( "Failed to save to {}: {err}", path.display() );
+                if self.config.json {
+                    echo!( self.out(), "{}", json::message( "save_failed", &message ));
+                } else {
+                    echo!( self.err(), "{message}" );
+                }
+            },
+        }
+    }
+    fn export_and_hint( &self, path: &Path ) {
+        match self.history().write_to( &self.state, path ) {
+            Ok(()) => if self.config.json {
+                echo!( self.out(), "{}", json::message( "exported", &path.display().to_string() ));
+            } else {
+                echo!( self.out(), "Exported to {}", path.display() );
+            },
+            Err( err ) => {
+                let message = format!( "Failed to export to {}: {err}", path.display() );
+                if self.config.json {
+                    echo!( self.out(), "{}", json::message( "export_failed", &message ));
+                } else {
+                    echo!( self.err(), "{message}" );
+                }
+            },
+        }
+    }
+    fn hint_replay_failed( &self, path: &Path, err: LoadError ) {
+        let message = format!( "Failed to replay {}: {err}", path.display() );
+        if self.config.json {
+            echo!( self.out(), "{}", json::message( "replay_failed", &message ));
+        } else {
+            echo!( self.err(), "{message}" );
+        }
+    }
+    fn show_recorded_hand( &self, record: &HandRecord ) {
+        if self.config.json {
+            for who in self.players() {
+                echo!( self.out(), "{}", json::hand( who, record.hands[ who ]));
+            }
+            return;
+        }
+        echo!( self.out(), "Hand {}", record.deal );
+        let width = self.players().map( |who| self.name_of( who ).len() ).max().unwrap_or_default();
+        for who in self.players() {
+            echo!( self.out(), "{:<width$} = {}", self.name_of( who ), self.text_of( record.hands[ who ]));
+        }
+        for who in self.players() {
+            if record.passes[ who ] != NO_CARD {
+                let receivers = self.pass_receivers( who ).into_iter().map( |receiver| self.name_of( receiver )).collect::<Vec<_>>();
+                echo!( self.out(), "{:<width$} passes {} to {}",
+                    self.name_of( who ), self.text_of( record.passes[ who ]), receivers.join( ", " ));
+            }
+        }
+    }
+    fn wait_for_next_trick( &self ) -> bool {
+        if self.config.automatic {
+            return true;
+        }
+        if self.config.json {
+            echo!( self.out(), "{}", json::prompt( "next", NO_CARD ));
+        } else {
+            echo!( self.out(), "Press enter for the next trick..." );
+        }
+        let mut buffer = String::new();
+        self.altio.input().read_line( &mut buffer ).unwrap();
+        buffer.trim() != "exit"
+    }
+    fn undo_and_hint( &mut self ) -> bool {
+        let message = if self.config.no_undo {
+            "Undo is disabled."
+        } else if self.undo() {
+            if self.config.json {
+                echo!( self.out(), "{}", json::message( "undone", "Back to before your last move." ));
+            } else {
+                echo!( self.out(), "Back to before your last move." );
+            }
+            return true;
+        } else {
+            "Nothing to undo."
+        };
+        if self.config.json {
+            echo!( self.out(), "{}", json::message( "undo_failed", message ));
+        } else {
+            echo!( self.err(), "{message}" );
+        }
+        false
     }
     fn hint_received_cards( &self, received: Cards ) {
         echo!( self.out(), "Received {}", self.text_of( received ));
-        if self.who_holds_two_of_clubs() != ME {
+        if self.who_holds_opening_card() != ME {
             self.show_my_hand();
         }
     }
 }
 
-impl Game {
+/// The built-in strategy, played on the game state observed by the player, see `GameState::observed_by()`.
+impl GameState {
     fn pick_cards( &self, who: usize ) -> Cards {
         if self.round == 0 {
-            self.players[who].hand.pick_three_cards()
+            let keep = if self.rule_set.jack_of_diamonds { JACK_OF_DIAMONDS } else { NO_CARD };
+            let hand = self.player(who).hand;
+            if hand.may_shoot_the_moon( self.deck() - hand ) {
+                hand.pick_three_cards_for_moon( keep )
+            } else {
+                hand.pick_three_cards_to( keep, self.pass_receivers( who )[..] == [ ( who + 1 ) % self.seats ])
+            }
         } else {
             self.pick_card( who )
         }.dest
     }
-    fn pick_cards_for( &self, who: usize ) -> Transfer {
-        self.hand.set( self.players[who].hand );
-        let mut cards = self.strategies.of[who]( self, who );
+    /// The only player who has taken points in this hand, other than the player and the partner,
+    /// if the points are more than half of the moon points.
+    fn moon_threat( &self, who: usize ) -> Option<usize> {
+        let mut takers = self.players().filter( |&i| self.player(i).hand_score != 0 );
+        match ( takers.next(), takers.next() ) {
+            ( Some( i ), None ) if i != who && i != self.partner( who )
+                && self.player(i).hand_score * 2 > self.rule_set.moon_points() => Some( i ),
+            _ => None,
+        }
+    }
+    /// Checks if the player goes for shooting the moon: nobody else has taken points and the hand is strong enough.
+    fn shooting_the_moon( &self, who: usize ) -> bool {
+        let hand = self.player(who).hand;
+        self.players().all( |i| i == who || self.player(i).hand_score == 0 )
+            && hand.may_shoot_the_moon( self.deck() - self.discarded - hand )
+    }
+    /// Picks the card to shoot the moon: leads the card nobody can beat, or the highest one; follows suit by
+    /// the highest card if it wins, otherwise the lowest one; discards the lowest card without points when void.
+    fn pick_card_for_moon( &self, who: usize ) -> Option<Transfer> {
+        let hand = self.player(who).hand;
+        let unseen = self.deck() - self.discarded - hand;
+        let rank = |card: Cards| card.suit_and_rank().1;
+        let card = if self.under_the_gun == who {
+            let sure = |card: Cards| unseen & card.suit_mask() & Cards( !( card.0 | ( card.0 - 1 ))) == NO_CARD;
+            self.legal_plays( who ).iter()
+                .max_by_key( |&card| ( sure( card ), rank( card ), card.0 ))?
+        } else if hand & self.suit_mask != NO_CARD {
+            let legal = self.legal_plays( who );
+            let highest = legal.iter().next_back()?;
+            if highest > self.high_card { highest } else { legal.iter().next()? }
+        } else {
+            let penalty = self.rule_set.penalty_cards();
+            ( self.legal_plays( who ) - penalty ).iter()
+                .min_by_key( |&card| ( rank( card ), card.0 ))?
+        };
+        self.transfer_card( who, card )
+    }
+    fn pick_card( &self, who: usize ) -> Transfer {
+        let hand = self.player(who).hand;
+        if self.shooting_the_moon( who ) {
+            if let Some( transfered ) = self.pick_card_for_moon( who ) {
+                return transfered;
+            }
+        }
+        let trick = self.players().fold( NO_CARD, |trick, i| trick + self.player(i).discarding );
+        let stopping = self.moon_threat( who ).is_some_and( |shooter| shooter == self.round_winner ) && self.points_of( trick ) > 0;
+
+        if self.under_the_gun == who { // early hand
+            if self.round == 1 {
+                if self.rule_set.any_opening_suit && !self.rule_set.points_on_first { // get rid of a high card for free
+                    let card = self.legal_plays( who ).iter().next_back().unwrap();
+                    return self.transfer_card( who, card ).unwrap();
+                }
+                return self.transfer_card( who, self.opening_card ).unwrap();
+            }
+            if self.rule_set.jack_of_diamonds && hand.contains( JACK_OF_DIAMONDS ) { // lead the Jack of Diamonds if nobody can beat it
+                let higher = QUEEN_OF_DIAMONDS + KING_OF_DIAMONDS + ACE_OF_DIAMONDS;
+                if higher - self.discarded - hand == NO_CARD {
+                    return self.transfer_card( who, JACK_OF_DIAMONDS ).unwrap();
+                }
+            }
+
+            let mut suits_cnts = [
+                ( CLUB   , hand.count_suit( CLUBS )),
+                ( DIAMOND, hand.count_suit( DIAMONDS )),
+                ( SPADE  , if hand & SPADES >= QUEEN_OF_SPADES {14} else {1} ),
+                ( HEART  , if self.hearts_may_lead() { hand.count_suit(HEARTS) } else {15} ),
+            ];
+            suits_cnts.sort_by_key( |suit_cnt| suit_cnt.1 ); // prefer discarding short suits than long suits
+
+            let legal = self.legal_plays( who );
+            for (suit,_) in suits_cnts {
+                for rank in RANKS {
+                    let card = Cards::of( rank, suit );
+                    if legal.contains( card ) {
+                        return self.transfer_card( who, card ).unwrap();
+                    }
+                }
+            }
+        } else if hand & self.suit_mask != NO_CARD { // follow suit
+            let suit = self.suit();
+            let legal = self.legal_plays( who ); // no points in the first trick unless held alone
+            let mut late_hand_follows = false;
+            for i in 1..self.seats-1 {
+                let i = (who+i) % self.seats;
+                if i == self.under_the_gun {
+                    break;
+                }
+                if self.player(i).may_have( suit ) {
+                    late_hand_follows = true;
+                }
+            }
+            if self.rule_set.jack_of_diamonds && self.suit_mask == DIAMONDS { // try to win the Jack of Diamonds
+                if trick.contains( JACK_OF_DIAMONDS ) && self.points_of( trick ) < 0 {
+                    if let Some( card ) = ( hand & DIAMONDS ).iter().next_back() {
+                        if card > self.high_card {
+                            return self.transfer_card( who, card ).unwrap();
+                        }
+                    }
+                }
+                if hand.contains( JACK_OF_DIAMONDS ) && JACK_OF_DIAMONDS > self.high_card
+                    && !late_hand_follows && self.points_of( trick + JACK_OF_DIAMONDS ) < 0
+                {
+                    return self.transfer_card( who, JACK_OF_DIAMONDS ).unwrap();
+                }
+            }
+            if stopping { // take the points to stop the moon
+                if let Some( card ) = legal.iter().next_back().filter( |&card| card > self.high_card ) {
+                    return self.transfer_card( who, card ).unwrap();
+                }
+            }
+            let partner_wins = self.round_winner == self.partner( who );
+            let mut to_discard = Cards::of( ACE, suit );
+            let mut high_card_to_discard = NO_CARD;
+            for rank in RANKS.rev() {
+                let card = Cards::of( rank, suit );
+                if legal.contains( card ) {
+                    if card < self.high_card {
+                        if self.rule_set.jack_of_diamonds && card == JACK_OF_DIAMONDS
+                            && hand & DIAMONDS & Cards( JACK_OF_DIAMONDS.0 - 1 ) != NO_CARD
+                        { // keep the Jack of Diamonds, discarding a lower diamond
+                            continue;
+                        }
+                        if partner_wins && card == QUEEN_OF_SPADES
+                            && hand & SPADES & Cards( QUEEN_OF_SPADES.0 - 1 ) != NO_CARD
+                        { // keep the Queen of Spades away from the partner, discarding a lower spade
+                            continue;
+                        }
+                        if self.suit_mask == SPADES
+                            && legal.contains( QUEEN_OF_SPADES )
+                            && QUEEN_OF_SPADES < self.high_card
+                            && !partner_wins
+                        {
+                            return self.transfer_card( who, QUEEN_OF_SPADES ).unwrap();
+                        }
+                        return self.transfer_card( who, card ).unwrap();
+                    }
+                    if to_discard >= card {
+                        to_discard = card;
+                        if high_card_to_discard < card && suit != HEART && !self.rule_set.penalty_cards().contains( card ) {
+                            high_card_to_discard = card;
+                        }
+                    }
+                }
+            }
+            return self.transfer_card(
+                who,
+                if late_hand_follows || high_card_to_discard == NO_CARD { to_discard } else { high_card_to_discard }
+            ).unwrap();
+        } else { // don't follow suit
+            let keep_points = self.round_winner == self.partner( who ) // no points to the partner or the moon shooter
+                || self.moon_threat( who ) == Some( self.round_winner );
+            let points_allowed = self.round != 1 || self.rule_set.points_on_first;
+            if hand.contains( QUEEN_OF_SPADES ) && points_allowed && !keep_points { // try to discard Queen of Spades
+                return self.transfer_card( who, QUEEN_OF_SPADES ).unwrap();
+            }
+            if hand.contains_suit( HEARTS ) && points_allowed && !keep_points { // try to discard hearts
+                for rank in RANKS.rev() {
+                    if let Some( transfered ) = self.transfer_card( who, Cards::of( rank, HEART )) {
+                        return transfered;
+                    }
+                }
+            }
+            for rank in RANKS.rev() { // try to discard high card
+                for suit in [SPADE, DIAMOND, CLUB] {
+                    let card = Cards::of( rank, suit );
+                    if self.rule_set.penalty_cards().contains( card ) && ( !points_allowed || keep_points ) {
+                        continue;
+                    }
+                    if card == JACK_OF_DIAMONDS && self.rule_set.jack_of_diamonds {
+                        continue;
+                    }
+                    if let Some( transfered ) = self.transfer_card( who, card ) {
+                        return transfered;
+                    }
+                }
+            }
+            if let Some( transfered ) = self.transfer_card( who, JACK_OF_DIAMONDS ) {
+                return transfered;
+            }
+            for rank in RANKS.rev() { // all hearts
+                if let Some( transfered ) = self.transfer_card( who, Cards::of( rank, HEART )) {
+                    return transfered;
+                }
+            }
+            for rank in RANKS.rev() { // penalty spades
+                if let Some( transfered ) = self.transfer_card( who, Cards::of( rank, SPADE )) {
+                    return transfered;
+                }
+            }
+        }
+        unreachable!();
+    }
+    fn transfer_card( &self, who: usize, card: Cards ) -> Option<Transfer> {
+        self.player(who).hand.transfer( card )
+    }
+}
+
+impl Game {
+    /// Shuffles a deck with the game's own random number generator and deals cards to players,
+    /// see `Action::Deal`.
+    pub fn deal_cards( &mut self ) -> Result<Vec<Event>, Rule> {
+        let hands = Cards::deal_among( self.seats, &mut self.rng );
+        self.apply( Action::Deal{ hands })
+    }
+    /// Applies the action of the human player, which can be taken back by `Game::undo()`
+    /// unless `Config::no_undo` is set.
+    pub fn apply_undoable( &mut self, action: Action ) -> Result<Vec<Event>, Rule> {
+        let snapshot = ( self.state.clone(), self.rng.clone() );
+        let events = self.apply( action )?;
+        if !self.config.no_undo {
+            self.undos.push( snapshot );
+        }
+        Ok( events )
+    }
+    /// Rolls the game back to before the last action applied by `Game::apply_undoable()`,
+    /// including the random number generator so that the following deals are the same.
+    ///
+    /// Returns `false` if there is nothing to undo.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut game = Game::with_config( cli::Config::from_args([ "heartless", "--seed", "2024" ]));
+    /// game.apply( Action::NewGame ).unwrap();
+    /// game.deal_cards().unwrap();
+    ///
+    /// let cards = game.player( ME ).hand.pick_three_cards().dest;
+    /// game.apply_undoable( Action::Pass{ who: ME, cards }).unwrap();
+    /// assert!( game.has_passed( ME ));
+    ///
+    /// assert!( game.undo() );
+    /// assert!( !game.has_passed( ME ));
+    /// assert!( !game.undo() );
+    /// ```
+    pub fn undo( &mut self ) -> bool {
+        match self.undos.pop() {
+            Some(( state, rng )) => {
+                self.state = state;
+                self.rng = rng;
+                self.notify_undo();
+                true
+            },
+            None => false,
+        }
+    }
+    /// Saves the game, including the random number generator, to a file which can be loaded by `Game::load_from()`.
+    ///
+    /// The first line of the file is "heartless-save" followed by `SAVE_VERSION`.
+    pub fn save_to( &self, path: &Path ) -> io::Result<()> {
+        let mut text = format!( "heartless-save {SAVE_VERSION}\nstarted {}\nrng {}\n", self.started, self.rng.get_seed() );
+        self.state.write_lines( &mut text );
+        fs::write( path, text )
+    }
+    /// A new game state with the same rules as this game.
+    fn new_state( &self ) -> GameState {
+        let mut state = GameState::with_ending( self.ending );
+        state.rule_set = self.rule_set;
+        state.moon_rule = self.moon_rule;
+        state.shoot_the_sun = self.shoot_the_sun;
+        state.pass_schedule = self.pass_schedule;
+        state.seats = self.seats;
+        state.opening_card = self.opening_card;
+        state.partnership = self.partnership;
+        state
+    }
+    /// Restores the game saved by `Game::save_to()`, after which the moves before cannot be undone.
+    /// The game is unchanged on failure.
+    pub fn load_from( &mut self, path: &Path ) -> Result<(), LoadError> {
+        let text = fs::read_to_string( path )?;
+        let mut lines = text.lines();
+
+        LoadError::check_header( lines.next().unwrap_or_default(), "heartless-save", SAVE_VERSION )?;
+
+        let mut state = self.new_state();
+        let mut started = false;
+        let mut rng = None;
+        for ( nth, line ) in lines.enumerate() {
+            let mut words = line.split_whitespace();
+            let parsed = match words.next() {
+                None => Some(()),
+                Some( "started" ) => words.next().and_then( |word| word.parse().ok() ).map( |value| started = value ),
+                Some( "rng"     ) => words.next().and_then( |word| word.parse().ok() ).map( |value| rng = Some( value )),
+                Some( _ ) => state.read_line( line ),
+            };
+            if parsed.is_none() {
+                return Err( LoadError::Invalid{ line_no: nth+2, line: line.to_owned() });
+            }
+        }
+
+        self.state = state;
+        self.started = started;
+        self.undos.clear();
+        if let Some( seed ) = rng {
+            self.rng.seed( seed );
+        }
+        Ok(())
+    }
+    /// Steps through the game recorded in the replay file trick by trick, see `History::write_to()`.
+    ///
+    /// The recorded actions are applied to a new game state and shown as if they were being played.
+    pub fn replay( &mut self, path: &Path ) -> Result<(), LoadError> {
+        let ( rules, history ) = History::read_from( path )?;
+        let actions = history.actions();
+        let last = actions.len();
+
+        self.state = rules;
+        for ( nth, action ) in actions.into_iter().enumerate() {
+            let events = self.apply( action ).map_err( |rule| LoadError::Illegal{ action, rule })?;
+            let mut trick_won = false;
+            for event in &events {
+                match *event {
+                    Event::Dealt{ deal, .. } => if let Some( record ) = history.hands.iter().find( |record| record.deal == deal ) {
+                        self.show_recorded_hand( record );
+                    },
+                    Event::TrickWon{ .. } => trick_won = true,
+                    _ => (),
+                }
+            }
+            self.show_events( events );
+            if trick_won && nth+1 != last && !self.wait_for_next_trick() {
+                break;
+            }
+        }
+        Ok(())
+    }
+    /// Solves the last tricks of each hand recorded in the replay file by the double-dummy solver, see `Solver`.
+    ///
+    /// When `Config::analyze_tricks` tricks are left, the remaining hands are shown with the fewest points each player
+    /// can be forced to take and the optimal line for each player. Then each recorded card that can be forced to take
+    /// more points than the best one is reported.
+    pub fn analyze( &mut self, path: &Path ) -> Result<(), LoadError> {
+        let ( rules, history ) = History::read_from( path )?;
+        let mut solver = Solver::default();
+        let mut deviated = None;
+
+        self.state = rules;
+        for action in history.actions() {
+            if let Action::Discard{ who, card } = action {
+                if self.rounds() - self.round < self.config.analyze_tricks {
+                    if deviated.is_none() {
+                        self.show_solution( &mut solver );
+                        deviated = Some( false );
+                    }
+                    if self.show_deviation( &mut solver, who, card ) {
+                        deviated = Some( true );
+                    }
+                }
+            }
+            let events = self.apply( action ).map_err( |rule| LoadError::Illegal{ action, rule })?;
+            if events.iter().any( |event| matches!( event, Event::HandScored{ .. })) {
+                if deviated == Some( false ) {
+                    echo!( self.out(), "No deviation from the optimal lines" );
+                }
+                deviated = None;
+            }
+        }
+        Ok(())
+    }
+    /// Shows whether the card can be forced to take more points than the best one, and returns true if so.
+    fn show_deviation( &self, solver: &mut Solver, who: usize, card: Cards ) -> bool {
+        let values = solver.card_values( &self.state, who );
+        let taken = values.iter().find( |&&( played, _ )| played == card ).map( |&( _, value )| value );
+        match ( values.iter().min_by_key( |&&( _, value )| value ), taken ) {
+            ( Some( &( best_card, best )), Some( taken )) if taken > best => {
+                echo!( self.out(), "Trick {}: {} discarded {} and can be forced to take {taken} points, rather than {best} by {}",
+                    self.round, self.name_of( who ), self.text_of( card ), self.text_of( best_card ));
+                true
+            },
+            _ => false,
+        }
+    }
+    fn show_solution( &self, solver: &mut Solver ) {
+        echo!( self.out(), "Hand {}, trick {}", self.deal(), self.round );
+        let width = self.players().map( |who| self.name_of( who ).len() ).max().unwrap_or_default();
+        for who in self.players() {
+            echo!( self.out(), "{:<width$} = {}", self.name_of( who ), self.text_of( self.player( who ).hand ));
+        }
+        for who in self.players() {
+            let line = solver.optimal_line( &self.state, who );
+            let tricks = line.chunks( self.seats ).map( |trick| {
+                trick.iter().map( |&( _, card )| self.text_of( card )).collect::<Vec<_>>().join( " " )
+            }).collect::<Vec<_>>();
+            echo!( self.out(), "{:<width$} can be forced to take {} points: {}",
+                self.name_of( who ), solver.forced_points( &self.state, who ), tricks.join( " | " ));
+        }
+    }
+    /// The view of the player, which its strategy is given, see `PlayerView`.
+    pub fn view_of( &self, who: usize ) -> PlayerView<'_> {
+        PlayerView::new( self, who )
+    }
+    /// Picks the cards for the player to pass or discard by its strategy, see `Strategy`.
+    ///
+    /// The built-in strategy takes over if the picked cards break the rule.
+    pub fn pick_cards_for( &self, who: usize ) -> Transfer {
+        let hand = self.player(who).hand;
+        let mut cards = if self.strategies.scripted[ who ] {
+            self.pick_cards_by_script( who )
+        } else {
+            let view = self.view_of( who );
+            let mut strategy = self.strategies.of[ who ].borrow_mut();
+            if self.round == 0 { strategy.pass( &view )} else { strategy.play( &view )}
+        };
 
         #[cfg( feature = "test-replica" )]
         {
-            let expected = self.pick_cards(who);
+            let expected = self.observed_by( who ).pick_cards( who );
             if cards != expected {
                 panic!( "expect {expected}, got {cards}" );
             }
@@ -934,101 +1656,322 @@ impl Game {
 
         if let Some( rule ) = self.break_the_rule( who, cards ) {
             self.hint_break_the_rule( cards, rule );
-            cards = self.pick_cards( who );
+            cards = self.observed_by( who ).pick_cards( who );
         }
-        Transfer{ src: self.hand.get()-cards, dest: cards }
+        Transfer{ src: hand-cards, dest: cards }
+    }
+    /// Passes or discards the cards picked for the player. If they still break a rule, reports it and falls back to
+    /// the lowest legal cards, so that the game never waits on a refused pick.
+    fn apply_picked_for( &mut self, who: usize ) {
+        let passing = self.round == 0;
+        let action = |cards: Cards| if passing { Action::Pass{ who, cards }} else { Action::Discard{ who, card: cards }};
+        let cards = self.pick_cards_for( who ).dest;
+        let events = match self.apply( action( cards )) {
+            Ok( events ) => events,
+            Err( rule ) => {
+                self.hint_break_the_rule( cards, rule );
+                let fallback = if passing {
+                    self.legal_passes( who ).iter().take( 3 ).fold( NO_CARD, |cards, card| cards + card )
+                } else {
+                    self.legal_plays( who ).iter().next().unwrap_or_default()
+                };
+                self.apply( action( fallback )).expect( "the lowest legal cards break no rule" )
+            },
+        };
+        self.show_events( events );
     }
     /// Enters the game main loop.
     pub fn main_loop( &mut self ) {
-        self.config.seed.map( |seed| fastrand::seed( seed ));
+        if let Some( path ) = self.config.replay.clone() {
+            if let Err( err ) = self.replay( &path ) {
+                self.hint_replay_failed( &path, err );
+            }
+            return;
+        }
+        if let Some( path ) = self.config.analyze.clone() {
+            if let Err( err ) = self.analyze( &path ) {
+                echo!( self.err(), "Failed to analyze {}: {err}", path.display() );
+            }
+            return;
+        }
+
         self.hint_rules();
 
+        if self.config.cancellation {
+            self.play_cancellation();
+            return;
+        }
+
+        if let Some( path ) = self.config.load.clone() {
+            match self.load_from( &path ) {
+                Ok(()) => self.hint_loaded( &path ),
+                Err( err ) => self.hint_load_failed( &path, err ),
+            }
+        }
+
         'game: while self.config.count.map( |n| n > 0 ).unwrap_or( true ) {
-            self.hint_deal();
-            match self.input() {
-                Input::Start => {
-                    self.start();
-                    while self.get_winner() == NOBODY {
-                        self.deal();
-                        let mut hint_no_pass = false;
-                        let mut hint_discarding = false;
-                        if self.needs_pass_cards() {
+            if !self.started {
+                self.hint_deal();
+                match self.input() {
+                    Input::Start => {
+                        self.start();
+                        self.undos.clear();
+                        if let Ok( events ) = self.apply( Action::NewGame ) {
+                            self.show_events( events );
+                        }
+                    },
+                    Input::Exit => break 'game,
+                    Input::Save( path ) => {
+                        self.save_and_hint( &path );
+                        continue 'game;
+                    },
+                    Input::Export( path ) => {
+                        self.export_and_hint( &path );
+                        continue 'game;
+                    },
+                    _ => {
+                        self.hint_deal();
+                        continue 'game;
+                    },
+                }
+            }
+            'hand: while self.phase() != Phase::GameOver {
+                let mut hint_no_pass = false;
+                let mut hint_discarding = false;
+                if self.phase() == Phase::Dealing {
+                    if let Ok( events ) = self.deal_cards() {
+                        self.show_events( events );
+                    }
+                    if self.phase() == Phase::Discarding {
+                        if self.who_holds_opening_card() == ME {
+                            hint_no_pass = true;
+                        } else {
+                            self.show_my_hand();
+                            if !self.config.json {
+                                echo!( self.out(), "Do not need to pass cards." );
+                            }
+                        }
+                    }
+                }
+                if self.phase() == Phase::Passing {
+                    self.show_my_hand();
+                    'pass: while !self.has_passed( ME ) {
+                        self.hint_pass();
+                        match self.input() {
+                            Input::Pass( cards ) => match self.apply_undoable( Action::Pass{ who: ME, cards }) {
+                                Ok( events ) => {
+                                    self.show_events( events );
+                                    break 'pass;
+                                },
+                                Err( rule ) => self.hint_break_the_rule( cards, rule ),
+                            },
+                            Input::Exit => break 'game,
+                            Input::Save( path ) => self.save_and_hint( &path ),
+                            Input::Export( path ) => self.export_and_hint( &path ),
+                            Input::Undo if self.undo_and_hint() => continue 'hand,
+                            _ => (),
+                        }
+                    }
+                    for who in self.others() {
+                        self.apply_picked_for( who );
+                    }
+                    if self.who_holds_opening_card() != ME {
+                        hint_discarding = true;
+                    }
+                }
+                while self.phase() == Phase::Discarding {
+                    let who = self.next_to_discard();
+                    if who == ME {
+                        'discard: loop {
                             self.show_my_hand();
-                            'pass: loop {
-                                self.hint_pass();
-                                match self.input() {
-                                    Input::Pass( cards ) => if self.pass_cards( cards ) {
-                                        break 'pass;
+                            if hint_no_pass {
+                                hint_no_pass = false;
+                                self.hint_no_pass_discard();
+                            } else {
+                                self.hint_discard();
+                            }
+                            match self.input() {
+                                Input::Discard( card ) => match self.apply_undoable( Action::Discard{ who, card }) {
+                                    Ok( events ) => {
+                                        self.show_events( events );
+                                        break 'discard;
                                     },
-                                    Input::Exit => break 'game,
-                                    _ => (),
-                                }
+                                    Err( rule ) => self.hint_break_the_rule( card, rule ),
+                                },
+                                Input::Exit => break 'game,
+                                Input::Save( path ) => self.save_and_hint( &path ),
+                                Input::Export( path ) => self.export_and_hint( &path ),
+                                Input::Undo if self.undo_and_hint() => continue 'hand,
+                                _ => (),
                             }
-                            if self.who_holds_two_of_clubs() != ME {
-                                hint_discarding = true;
+                        }
+                    } else {
+                        if hint_discarding {
+                            hint_discarding = false;
+                            if !self.config.json {
+                                echo!( self.out(), "discarding..." );
                             }
-                        } else if self.who_holds_two_of_clubs() == ME {
-                            hint_no_pass = true;
-                        } else {
-                            self.show_my_hand();
-                            echo!( self.out(), "Do not need to pass cards." );
                         }
-                        while self.next_round() {
-                            let start = self.under_the_gun;
-                            for i in start..start+4 {
-                                let who = i % 4;
-                                if who == ME {
-                                    'discard: loop {
-                                        self.show_my_hand();
-                                        if hint_no_pass {
-                                            hint_no_pass = false;
-                                            self.hint_no_pass_discard();
-                                        } else {
-                                            self.hint_discard();
-                                        }
-                                        match self.input() {
-                                            Input::Discard( card ) => match self.break_the_rule( ME, card ) {
-                                                Some( rule ) => self.hint_break_the_rule( card, rule ),
-                                                None => {
-                                                    let transfered = self.players[ME].hand.transfer( card ).unwrap();
-                                                    self.discard( ME, transfered );
-                                                    if i != start+3 {
-                                                        self.show_scores_and_discarding( RoundStatus::Pending, ME );
-                                                    }
-                                                    break 'discard;
-                                                },
-                                            },
-                                            Input::Exit => break 'game,
-                                            _ => (),
-                                        }
-                                    }
-                                } else {
-                                    if hint_discarding {
-                                        hint_discarding = false;
-                                        echo!( self.out(), "discarding..." );
-                                    }
-                                    let cards = self.pick_cards_for(who);
-                                    self.discard( who, cards );
-                                    if i != start+3 {
-                                        self.show_scores_and_discarding( RoundStatus::Pending, who );
-                                    }
-                                }
+                        self.apply_picked_for( who );
+                    }
+                }
+            }
+            self.started = false;
+            if let Some( n ) = self.config.count.as_mut() {
+                *n -= 1;
+            }
+        }
+        let awards = self.players().map( |who| self.player( who ).awards ).collect::<Vec<_>>();
+        if self.config.json {
+            echo!( self.out(), "{}", json::statistics( &awards ));
+        } else {
+            let statistics = self.players().map( |who| match who {
+                ME => format!( "YOU won {}", awards[ who ]),
+                _  => format!( "{} won {}", self.name_of( who ).to_uppercase(), awards[ who ]),
+            }).collect::<Vec<_>>();
+            echo!( self.out(), "Statistics: {}.", statistics.join( ", " ));
+        }
+    }
+    /// Enters the main loop of Cancellation Hearts, see `CancellationState`.
+    fn play_cancellation( &mut self ) {
+        let mut state = CancellationState::new( self.config.players );
+        state.ending = self.ending;
+        state.rule_set = self.rule_set;
+
+        'game: while self.config.count.map( |n| n > 0 ).unwrap_or( true ) {
+            if !self.started {
+                self.hint_deal();
+                match self.input_cancellation( &state ) {
+                    Input::Start => {
+                        self.start();
+                        if let Ok( events ) = state.apply( CancellationAction::NewGame ) {
+                            self.show_cancellation_events( &state, events );
+                        }
+                    },
+                    Input::Exit => break 'game,
+                    _ => continue 'game,
+                }
+            }
+            while state.phase() != Phase::GameOver {
+                if state.phase() == Phase::Dealing {
+                    let ( hands, kitty ) = CancellationState::deal_with( state.seats, &mut self.rng );
+                    if let Ok( events ) = state.apply( CancellationAction::Deal{ hands, kitty }) {
+                        self.show_cancellation_events( &state, events );
+                    }
+                }
+                while state.phase() == Phase::Discarding {
+                    let who = state.next_to_discard();
+                    let card = if who == ME {
+                        loop {
+                            echo!( self.out(), "= {}", state.hand( ME ).text( self.text_flags() ));
+                            echo!( self.out(), "Discard 1 card, e.g. {}", state.pick_card( ME ));
+                            match self.input_cancellation( &state ) {
+                                Input::Discard( card ) => match state.break_the_rule( ME, card ) {
+                                    Some( rule ) => self.hint_break_the_rule( card, rule ),
+                                    None         => break card,
+                                },
+                                Input::Exit => break 'game,
+                                _ => (),
                             }
-                            self.keep_score();
-                            self.show_scores_and_discarding( RoundStatus::Complete, (start+3)%4 );
                         }
+                    } else {
+                        self.sleep_in_interactive_mode();
+                        state.pick_card( who )
+                    };
+                    if let Ok( events ) = state.apply( CancellationAction::Discard{ who, card }) {
+                        self.show_cancellation_events( &state, events );
                     }
-                    self.show_winner();
-                    self.reset();
-                    self.config.count.as_mut().map( |n| *n -= 1 );
-                    continue 'game;
+                }
+            }
+            self.started = false;
+            if let Some( n ) = self.config.count.as_mut() {
+                *n -= 1;
+            }
+        }
+        let statistics = state.players().map( |who| match who {
+            ME => format!( "YOU won {}", state.awards( who )),
+            _  => format!( "{} won {}", Game::cancellation_name_of( &state, who ).to_uppercase(), state.awards( who )),
+        }).collect::<Vec<_>>();
+        echo!( self.out(), "Statistics: {}.", statistics.join( ", " ));
+    }
+    fn input_cancellation( &self, state: &CancellationState ) -> Input {
+        if self.config.automatic {
+            return if self.started { Input::Discard( state.pick_card( ME )) } else { Input::Start };
+        }
+        let mut buffer = String::new();
+        self.altio.input().read_line( &mut buffer ).unwrap();
+        match buffer.trim() {
+            "" => Input::Start,
+            "exit" => Input::Exit,
+            s if s == "undo" || s.split_whitespace().next().is_some_and( |cmd| cmd == "save" || cmd == "export" ) => {
+                echo!( self.err(), "\"{s}\" is not supported in Cancellation Hearts." );
+                Input::Invalid
+            },
+            s => match Cards::parse_in_hand( s, state.hand( ME ).distinct(), state.suit_mask() ) {
+                Ok( card ) if card.count() == 1 => Input::Discard( card ),
+                Ok( cards ) => {
+                    self.hint_discard_one( cards );
+                    Input::Invalid
+                },
+                Err( err ) => {
+                    self.handle_card_parse_error( s, err );
+                    Input::Invalid
                 },
-                Input::Exit => break 'game,
-                _ => self.hint_deal(),
+            },
+        }
+    }
+    /// The name of the player at the given seat in Cancellation Hearts, e.g. "player 3".
+    fn cancellation_name_of( state: &CancellationState, who: usize ) -> String {
+        match who {
+            ME                            => "me".to_owned(),
+            LEFT                          => "left".to_owned(),
+            _ if who == state.seats-1     => "right".to_owned(),
+            _                             => format!( "player {}", who+1 ),
+        }
+    }
+    fn show_cancellation_events( &self, state: &CancellationState, events: Vec<CancellationEvent> ) {
+        for event in events {
+            match event {
+                CancellationEvent::Discarded{ who:_, card:_ } if state.next_to_discard() == ME && state.phase() == Phase::Discarding =>
+                    self.show_cancellation_trick( state, &state.trick(), RoundStatus::Pending ),
+                CancellationEvent::TrickWon{ trick, points } => {
+                    self.show_cancellation_trick( state, &trick, RoundStatus::Complete );
+                    match trick.winner {
+                        Some( winner ) => if trick.round == 1 && !state.kitty().is_empty() {
+                            echo!( self.out(), "The kitty {} goes to {}.", state.kitty().text( self.text_flags() ),
+                                Game::cancellation_name_of( state, winner ));
+                        },
+                        None => if trick.round == state.rounds() {
+                            echo!( self.out(), "All the cards of the suit led cancelled, and {points} points are lost." );
+                        } else {
+                            echo!( self.out(), "All the cards of the suit led cancelled, and {points} points are held over." );
+                        },
+                    }
+                },
+                CancellationEvent::ShotTheMoon{ who } => {
+                    let shooter = match who {
+                        ME => "I".to_owned(),
+                        _  => format!( "The {} player", Game::cancellation_name_of( state, who )),
+                    };
+                    echo!( self.out(), "{shooter} shot the moon, +{} points to the others!", 2 * state.rule_set.moon_points() );
+                },
+                CancellationEvent::GameOver{ winner: ME } => echo!( self.out(), "The winner is me!" ),
+                CancellationEvent::GameOver{ winner } =>
+                    echo!( self.out(), "The winner is the {} player!", Game::cancellation_name_of( state, winner )),
+                _ => (),
             }
         }
-        let p = &self.players;
-        echo!( self.out(), "Statistics: YOU won {}, LEFT won {}, OPPOSITE won {}, RIGHT won {}.",
-            p[ME].awards, p[LEFT].awards, p[OPPOSITE].awards, p[RIGHT].awards );
+    }
+    fn show_cancellation_trick( &self, state: &CancellationState, trick: &CancellationTrick, round_status: RoundStatus ) {
+        let mut out = self.out();
+        let mark = [" ", "."][ round_status as usize ];
+        let splitter = ( 1..=state.rounds() ).map( |round| if round == trick.round { mark } else { "-" }).collect::<Vec<_>>();
+        echo!( out, "{}", splitter.join( " " ));
+        for who in state.players() {
+            let gun = if who == trick.leader { ':' } else { ' ' };
+            let win = if Some( who ) == trick.winner { '.' } else { ' ' };
+            echo!( out, "{:<12} {:>7}  {gun}{}{win}", Game::cancellation_name_of( state, who ),
+                format!( "{}/{}", state.hand_score( who ), state.game_score( who )), trick.cards[ who ].text( self.text_flags() ));
+        }
     }
 }
@@ -0,0 +1,237 @@
+//! The complete record of a game, hand by hand and trick by trick, and its replay files.
+//!
+//! A `History` is recorded by `GameState::apply()` from the resulting events, and can be replayed
+//! by applying `History::actions()` to a new game state.
+
+use crate::*;
+
+use std::{fs, io, path::Path};
+
+/// The version of the files written by `History::write_to()`.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// The file to export the replay if the "export" command does not specify one.
+pub const DEFAULT_REPLAY_FILE: &str = "heartless.replay";
+
+/// The record of one hand, from dealing to scoring.
+#[derive( Clone, Debug, Default, PartialEq, Eq )]
+pub struct HandRecord {
+    /// Which hand of the game it is, 1~.
+    pub deal        : usize,
+    /// The cards dealt to each player, or `NO_CARD` for the seats not taken.
+    pub hands       : [Cards;MAX_PLAYERS],
+    /// The cards passed by each player, or `NO_CARD` if not passed.
+    pub passes      : [Cards;MAX_PLAYERS],
+    /// The completed tricks, with the points taken by their winners.
+    pub tricks      : Vec<(Trick,i32)>,
+    /// The scores of each player in this hand, valid after all the tricks.
+    pub hand_scores : [i32;MAX_PLAYERS],
+    /// The scores of each player in this game, valid after all the tricks.
+    pub game_scores : [i32;MAX_PLAYERS],
+}
+
+impl HandRecord {
+    /// The number of players in this hand.
+    pub fn seats( &self ) -> usize {
+        self.hands.iter().filter( |&&hand| hand != NO_CARD ).count()
+    }
+    /// How many tricks are played in this hand.
+    pub fn rounds( &self ) -> usize {
+        self.hands[ ME ].count() as usize
+    }
+}
+
+/// The record of one game.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = GameState::default();
+/// state.apply( Action::NewGame ).unwrap();
+/// let hands = Cards::deal_with( &mut Rng::with_seed( 2024 ));
+/// state.apply( Action::Deal{ hands }).unwrap();
+///
+/// let record = &state.history().hands[0];
+/// assert_eq!( record.deal, 1 );
+/// assert_eq!( record.hands, hands );
+/// assert!( record.tricks.is_empty() );
+///
+/// let mut replayed = GameState::default();
+/// state.history().actions().into_iter().for_each( |action| { replayed.apply( action ).unwrap(); });
+/// assert_eq!( replayed.history(), state.history() );
+/// ```
+#[derive( Clone, Debug, PartialEq, Eq )]
+pub struct History {
+    /// The hands played so far.
+    pub hands  : Vec<HandRecord>,
+    /// The winner of the game, or `NOBODY` if the game is not over.
+    pub winner : usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History{ hands: Vec::new(), winner: NOBODY }
+    }
+}
+
+impl History {
+    /// Records what the event tells.
+    pub fn record( &mut self, event: &Event ) {
+        match *event {
+            Event::GameStarted => *self = History::default(),
+            Event::Dealt{ deal, hands } => self.hands.push( HandRecord{ deal, hands, ..HandRecord::default() }),
+            Event::Passed{ who, cards } => if let Some( record ) = self.hands.last_mut() {
+                record.passes[ who ] = cards;
+            },
+            Event::TrickWon{ trick, points } => if let Some( record ) = self.hands.last_mut() {
+                record.tricks.push(( trick, points ));
+            },
+            Event::HandScored{ hand_scores, game_scores } => if let Some( record ) = self.hands.last_mut() {
+                record.hand_scores = hand_scores;
+                record.game_scores = game_scores;
+            },
+            Event::GameOver{ winner } => self.winner = winner,
+            _ => (),
+        }
+    }
+    /// The actions to apply to a default `GameState` to play the recorded game again.
+    ///
+    /// The cards discarded in a trick that has not completed are not recorded, thus not included.
+    pub fn actions( &self ) -> Vec<Action> {
+        let mut actions = vec![ Action::NewGame ];
+        for record in &self.hands {
+            actions.push( Action::Deal{ hands: record.hands });
+            let seats = record.seats();
+            for who in 0..seats {
+                if record.passes[ who ] != NO_CARD {
+                    actions.push( Action::Pass{ who, cards: record.passes[ who ]});
+                }
+            }
+            for ( trick, _ ) in &record.tricks {
+                for nth in 0..seats {
+                    let who = ( trick.leader + nth ) % seats;
+                    actions.push( Action::Discard{ who, card: trick.cards[ who ]});
+                }
+            }
+        }
+        actions
+    }
+    /// Writes the history in lines of a key followed by its values, see `History::read_line()`.
+    ///
+    /// Cards are written as hexadecimal numbers of their binary representation, one value for each player:
+    ///
+    /// - "hand", the deal and the cards dealt to each player;
+    /// - "pass", the cards passed by each player;
+    /// - "trick", the round, leader, winner, points and the card discarded by each player;
+    /// - "scored", the hand scores and game scores of each player;
+    /// - "game_over", the winner.
+    pub fn write_lines( &self, lines: &mut String ) {
+        let mut line = |line: String| { lines.push_str( &line ); lines.push( '\n' ); };
+        for record in &self.hands {
+            let seats = record.seats();
+            let cards = |cards: &[Cards]| cards[ ..seats ].iter().map( |cards| format!( " {:#x}", cards.0 )).collect::<String>();
+            let scores = |scores: &[i32]| scores[ ..seats ].iter().map( |score| format!( " {score}" )).collect::<String>();
+            line( format!( "hand {}{}", record.deal, cards( &record.hands )));
+            line( format!( "pass{}", cards( &record.passes )));
+            for ( trick, points ) in &record.tricks {
+                line( format!( "trick {} {} {} {points}{}", trick.round, trick.leader, trick.winner, cards( &trick.cards )));
+            }
+            if record.tricks.len() == record.rounds() {
+                line( format!( "scored{}{}", scores( &record.hand_scores ), scores( &record.game_scores )));
+            }
+        }
+        if self.winner != NOBODY {
+            line( format!( "game_over {}", self.winner ));
+        }
+    }
+    /// Restores a line written by `History::write_lines()`, or returns `None` if the line is malformed.
+    pub fn read_line( &mut self, line: &str ) -> Option<()> {
+        fn cards( word: Option<&str> ) -> Option<Cards> {
+            u64::from_str_radix( word?.strip_prefix( "0x" )?, 16 ).ok().map( Cards )
+        }
+        fn number<T: std::str::FromStr>( word: Option<&str> ) -> Option<T> {
+            word?.parse().ok()
+        }
+
+        let mut words = line.split_whitespace();
+        match words.next()? {
+            "hand" => {
+                let deal = number( words.next() )?;
+                let mut hands = [NO_CARD;MAX_PLAYERS];
+                let mut seats = 0;
+                for word in words.by_ref() {
+                    *hands.get_mut( seats )? = cards( Some( word ))?;
+                    seats += 1;
+                }
+                if seats < 3 {
+                    return None;
+                }
+                self.hands.push( HandRecord{ deal, hands, ..HandRecord::default() });
+            },
+            "pass" => {
+                let record = self.hands.last_mut()?;
+                let seats = record.seats();
+                for pass in &mut record.passes[ ..seats ] {
+                    *pass = cards( words.next() )?;
+                }
+            },
+            "trick" => {
+                let record = self.hands.last_mut()?;
+                let seats = record.seats();
+                let mut trick = Trick {
+                    round  : number( words.next() )?,
+                    leader : number( words.next() )?,
+                    winner : number( words.next() )?,
+                    cards  : [NO_CARD;MAX_PLAYERS],
+                };
+                let points = number( words.next() )?;
+                for card in &mut trick.cards[ ..seats ] {
+                    *card = cards( words.next() )?;
+                }
+                if trick.leader >= seats || trick.winner >= seats {
+                    return None;
+                }
+                record.tricks.push(( trick, points ));
+            },
+            "scored" => {
+                let record = self.hands.last_mut()?;
+                let seats = record.seats();
+                for score in record.hand_scores[ ..seats ].iter_mut().chain( record.game_scores[ ..seats ].iter_mut() ) {
+                    *score = number( words.next() )?;
+                }
+            },
+            "game_over" => self.winner = number( words.next() )?,
+            _ => return None,
+        }
+        words.next().is_none().then_some(())
+    }
+    /// Writes the history to a replay file which can be read by `History::read_from()`.
+    ///
+    /// The first line of the file is "heartless-replay" followed by `REPLAY_VERSION`, then the rules of the game
+    /// the history is recorded by, see `GameState::write_rules()`.
+    pub fn write_to( &self, rules: &GameState, path: &Path ) -> io::Result<()> {
+        let mut text = format!( "heartless-replay {REPLAY_VERSION}\n" );
+        rules.write_rules( &mut text );
+        self.write_lines( &mut text );
+        fs::write( path, text )
+    }
+    /// Reads the replay file written by `History::write_to()`, returning a new game state of the rules recorded
+    /// together with the history.
+    pub fn read_from( path: &Path ) -> Result<( GameState, Self ), LoadError> {
+        let text = fs::read_to_string( path )?;
+        let mut lines = text.lines();
+
+        LoadError::check_header( lines.next().unwrap_or_default(), "heartless-replay", REPLAY_VERSION )?;
+
+        let mut rules = GameState::default();
+        let mut history = History::default();
+        for ( nth, line ) in lines.enumerate() {
+            if !line.trim().is_empty() && rules.read_rule( line ).or_else( || history.read_line( line )).is_none() {
+                return Err( LoadError::Invalid{ line_no: nth+2, line: line.to_owned() });
+            }
+        }
+        Ok(( rules, history ))
+    }
+}
@@ -0,0 +1,198 @@
+//! Information Set Monte Carlo Tree Search, the built-in strategy that grows one tree over all the hidden hands.
+//!
+//! The search is single-observer: the tree holds the cards discarded from the player's point of view. Each iteration
+//! deals the cards not seen yet to the other players as `Pimc` does, descends the tree by UCB1 among the cards
+//! legal in that deal, adds one card to the tree, plays the hand out with random cards and scores it, shooting the
+//! moon included. The card tried most often at the root is discarded.
+
+use crate::*;
+use crate::pimc::sample_hands;
+use crate::solver::Position;
+
+use std::time::{Duration, Instant};
+
+/// The iterations run for each card to discard, unless `Ismcts::millis` runs out.
+pub const DEFAULT_ISMCTS_ITERATIONS: usize = 2000;
+
+/// The exploration constant of UCB1, for the rewards of a hand ranging in about one.
+const EXPLORATION: f64 = 0.7;
+
+/// The budget of the Information Set Monte Carlo Tree Search strategy.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = GameState::default();
+/// state.pass_schedule = PassSchedule::NoPassing;
+/// state.apply( Action::Deal{ hands: Cards::deal_with( &mut Rng::with_seed( 2024 ))}).unwrap();
+///
+/// let ismcts = Ismcts{ iterations: 100, millis: None };
+/// let mut rng = Rng::with_seed( 2024 );
+/// while state.phase() == Phase::Discarding {
+///     let who = state.next_to_discard();
+///     let card = ismcts.pick_card( &state, who, &mut rng );
+///     state.apply( Action::Discard{ who, card }).unwrap();
+/// }
+/// assert_eq!( state.players().map( |who| state.player( who ).taken.count() ).sum::<u8>(), 52 );
+/// ```
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub struct Ismcts {
+    /// How many iterations to run.
+    pub iterations : usize,
+    /// Stops iterating after this many milliseconds, if given, with at least one iteration run.
+    pub millis     : Option<u64>,
+}
+
+impl Default for Ismcts {
+    fn default() -> Self {
+        Ismcts{ iterations: DEFAULT_ISMCTS_ITERATIONS, millis: None }
+    }
+}
+
+/// A card discarded in the tree, with the statistics of the player who discarded it.
+struct Node {
+    card     : Cards,
+    who      : usize,
+    children : Vec<usize>,
+    visits   : u32,
+    avails   : u32,
+    reward   : f64,
+}
+
+impl Node {
+    fn new( card: Cards, who: usize ) -> Self {
+        Node{ card, who, children: Vec::new(), visits: 0, avails: 1, reward: 0.0 }
+    }
+    fn ucb( &self ) -> f64 {
+        let visits = self.visits as f64;
+        self.reward / visits + EXPLORATION * ( ( self.avails as f64 ).ln() / visits ).sqrt()
+    }
+}
+
+impl Ismcts {
+    /// Picks the card for the player to discard, who must be the next to discard.
+    pub fn pick_card( &self, state: &GameState, who: usize, rng: &mut Rng ) -> Cards {
+        let candidates = state.legal_plays( who ).iter().collect::<Vec<_>>();
+        if candidates.len() <= 1 {
+            return candidates.first().copied().unwrap_or_default();
+        }
+
+        let started = Instant::now();
+        let penalty = state.rule_set.penalty_cards();
+        let mut tree = vec![ Node::new( NO_CARD, NOBODY )];
+        let mut path = Vec::new();
+        for nth in 0..self.iterations.max( 1 ) {
+            if nth > 0 && self.millis.is_some_and( |millis| started.elapsed() >= Duration::from_millis( millis )) {
+                break;
+            }
+            let mut position = Position::new( state, sample_hands( state, who, rng ));
+            let mut taken = [NO_CARD;MAX_PLAYERS];
+            state.players().for_each( |i| taken[i] = state.player(i).taken );
+            path.clear();
+            let mut node = 0;
+            let mut over = loop {
+                let legal = position.legal( state );
+                let tried = tree[ node ].children.iter().fold( NO_CARD, |cards, &child| cards + tree[ child ].card );
+                let untried = legal - tried;
+                if untried != NO_CARD {
+                    let card = random_card( untried, rng );
+                    tree.push( Node::new( card, position.next ));
+                    let child = tree.len() - 1;
+                    tree[ node ].children.push( child );
+                    path.push( child );
+                    break discard( state, &mut position, &mut taken, card );
+                }
+                let mut best = None;
+                for i in 0..tree[ node ].children.len() {
+                    let child = tree[ node ].children[i];
+                    if legal.contains( tree[ child ].card ) {
+                        tree[ child ].avails += 1;
+                        if best.is_none_or( |best: usize| tree[ child ].ucb() > tree[ best ].ucb() ) {
+                            best = Some( child );
+                        }
+                    }
+                }
+                node = best.unwrap_or_default();
+                path.push( node );
+                if discard( state, &mut position, &mut taken, tree[ node ].card ) {
+                    break true;
+                }
+            };
+            while !over {
+                let card = random_card( position.legal( state ), rng );
+                over = discard( state, &mut position, &mut taken, card );
+            }
+
+            let rewards = rewards_of( state, &taken, penalty );
+            for &node in &path {
+                tree[ node ].visits += 1;
+                tree[ node ].reward += rewards[ tree[ node ].who ];
+            }
+        }
+        tree[0].children.iter()
+            .max_by_key( |&&child| tree[ child ].visits )
+            .map( |&child| tree[ child ].card )
+            .unwrap_or( candidates[0] )
+    }
+}
+
+/// Discards the card by the next player, and returns true if the hand is over.
+fn discard( state: &GameState, position: &mut Position, taken: &mut [Cards;MAX_PLAYERS], card: Cards ) -> bool {
+    if !position.play( card, state ) {
+        return false;
+    }
+    taken[ position.winner ] += position.played();
+    if position.round >= state.rounds() {
+        return true;
+    }
+    position.next_trick();
+    false
+}
+
+fn random_card( cards: Cards, rng: &mut Rng ) -> Cards {
+    cards.iter().nth( rng.usize( ..cards.count() as usize )).unwrap_or_default()
+}
+
+/// The reward of each player at the end of the hand, one minus the points taken by the player's team
+/// relative to the moon points, with shooting the moon adding the moon points to the other teams, see
+/// `GameState::moon_taken()`.
+fn rewards_of( state: &GameState, taken: &[Cards;MAX_PLAYERS], penalty: Cards ) -> [f64;MAX_PLAYERS] {
+    let moon_points = state.rule_set.moon_points();
+    let team_taken = |team: usize| state.players().filter( |&i| state.team_of( i ) == team ).fold( NO_CARD, |cards, i| cards + taken[i] );
+    let shooter = state.players().find( |&i| state.moon_taken( i, taken ) & penalty == penalty ).map( |i| state.team_of( i ));
+    let mut rewards = [0.0;MAX_PLAYERS];
+    for i in state.players() {
+        let team = state.team_of( i );
+        let points = match shooter {
+            Some( shooter ) if shooter == team => state.points_of( team_taken( team ) - penalty ),
+            Some( _ )                          => state.points_of( team_taken( team ) - penalty ) + moon_points,
+            None                               => state.points_of( team_taken( team )),
+        };
+        rewards[i] = 1.0 - points as f64 / moon_points as f64;
+    }
+    rewards
+}
+
+#[cfg( test )]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewards_the_moon_as_partners_shoot_it() {
+        let mut taken = [NO_CARD;MAX_PLAYERS];
+        taken[ ME ] = HEARTS;
+        taken[ OPPOSITE ] = QUEEN_OF_SPADES;
+        let penalty = QUEEN_OF_SPADES + HEARTS;
+
+        let mut state = GameState::default();
+        for ( partnership, rewards ) in [
+            ( Partnership::JointMoon, [ 1.0, 0.0, 1.0, 0.0 ]),
+            ( Partnership::SoloMoon , [ 0.0, 1.0, 0.0, 1.0 ]),
+        ] {
+            state.partnership = partnership;
+            assert_eq!( rewards_of( &state, &taken, penalty )[ ..4 ], rewards );
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! JSON Lines representation of game events, for UI clients to consume instead of console text.
+//!
+//! Each event is printed in one line as a JSON object with an "event" field telling its kind.
+//! Cards are represented as strings, e.g. "2c 0h Qs", which can be parsed by `Cards::from_str()`.
+
+use crate::{
+    card::*,
+    game::Rule,
+    state::*,
+    view::PlayerView,
+};
+
+const JSON_CARD: u32 = ASCII_SUIT | NO_SHARED_SUIT;
+
+/// Quotes and escapes a string as a JSON string.
+pub fn string( s: &str ) -> String {
+    let mut quoted = String::with_capacity( s.len()+2 );
+    quoted.push( '"' );
+    for ch in s.chars() {
+        match ch {
+            '"'  => quoted.push_str( "\\\"" ),
+            '\\' => quoted.push_str( "\\\\" ),
+            '\n' => quoted.push_str( "\\n" ),
+            '\r' => quoted.push_str( "\\r" ),
+            '\t' => quoted.push_str( "\\t" ),
+            ch if ch < ' ' => quoted.push_str( &format!( "\\u{:04x}", ch as u32 )),
+            ch => quoted.push( ch ),
+        }
+    }
+    quoted.push( '"' );
+    quoted
+}
+
+/// Represents cards as a JSON string.
+pub fn cards( cards: Cards ) -> String {
+    string( &cards.text( JSON_CARD ))
+}
+
+/// Represents numbers as a JSON array.
+pub fn array<T: ToString>( items: impl IntoIterator<Item=T> ) -> String {
+    let items = items.into_iter().map( |item| item.to_string() ).collect::<Vec<_>>();
+    format!( "[{}]", items.join( "," ))
+}
+
+/// The snake case name of the rule.
+pub fn rule_name( rule: Rule ) -> &'static str {
+    match rule {
+        Rule::OutOfTurn         => "out_of_turn",
+        Rule::PassInHand        => "pass_in_hand",
+        Rule::PassThreeCards    => "pass_three_cards",
+        Rule::DiscardInHand     => "discard_in_hand",
+        Rule::DiscardOneCard    => "discard_one_card",
+        Rule::TwoOfClubs        => "two_of_clubs",
+        Rule::FollowSuit        => "follow_suit",
+        Rule::Heartbreak        => "heartbreak",
+        Rule::HeartInFirstRound => "heart_in_first_round",
+        Rule::QueenInFirstRound => "queen_in_first_round",
+    }
+}
+
+/// The snake case name of the moon rule.
+pub fn moon_rule_name( rule: MoonRule ) -> &'static str {
+    match rule {
+        MoonRule::AddToOthers      => "add_to_others",
+        MoonRule::SubtractFromSelf => "subtract_from_self",
+        MoonRule::ShooterChooses   => "shooter_chooses",
+    }
+}
+
+/// The event of a message, e.g. "invalid_input".
+pub fn message( event: &str, message: &str ) -> String {
+    format!( r#"{{"event":"{event}","message":{}}}"#, string( message ))
+}
+
+/// The event of a player's hand.
+pub fn hand( who: usize, hand: Cards ) -> String {
+    format!( r#"{{"event":"hand","who":{who},"cards":{}}}"#, cards( hand ))
+}
+
+/// The event of breaking the rule.
+pub fn rule_violation( rule: Rule, cards: Cards ) -> String {
+    format!( r#"{{"event":"rule_violation","rule":"{}","cards":{}}}"#, rule_name( rule ), self::cards( cards ))
+}
+
+/// The event asking for the player's input, with the cards picked by the strategy as a hint.
+///
+/// The `action` is one of "start", "pass" and "discard".
+pub fn prompt( action: &str, hint: Cards ) -> String {
+    format!( r#"{{"event":"prompt","action":"{action}","hint":{}}}"#, cards( hint ))
+}
+
+/// The history of the current hand in the view of a player: the cards it passed and received,
+/// and the completed tricks with the cards in the order of discarding.
+pub fn history( view: &PlayerView ) -> String {
+    let tricks = view.tricks().iter().map( |( trick, points )| {
+        format!( r#"{{"round":{},"leader":{},"winner":{},"points":{points},"plays":{}}}"#,
+            trick.round, trick.leader, trick.winner, plays( trick, view.seats() ))
+    });
+    format!( r#"{{"passed":{},"received":{},"tricks":{}}}"#, cards( view.passed() ), cards( view.received() ), array( tricks ))
+}
+
+/// The cards discarded in the trick as a JSON array in the order of discarding, e.g. [{"who":3,"card":"2c"}].
+fn plays( trick: &Trick, seats: usize ) -> String {
+    array( ( 0..seats ).map( |nth| ( trick.leader + nth ) % seats )
+        .filter( |&who| trick.cards[ who ] != NO_CARD )
+        .map( |who| format!( r#"{{"who":{who},"card":{}}}"#, cards( trick.cards[ who ]))))
+}
+
+/// The fields of the player's view shared by the requests, see `PlayerView`.
+fn view( view: &PlayerView ) -> String {
+    let seats = 0..view.seats();
+    let trick = view.trick();
+    format!( r#""who":{},"hand":{},"legal":{},"round":{},"pass_to":{},"trick":{},"discarded":{},"suits":{},"hand_scores":{},"game_scores":{},"history":{}"#,
+        view.who(), cards( view.hand() ), cards( view.legal() ), view.round(), array( view.pass_receivers() ),
+        if trick.leader < view.seats() { plays( &trick, view.seats() )} else { "[]".to_owned() },
+        cards( view.discarded() ),
+        array( seats.clone().map( |who| string( view.suit_chars( who )))),
+        array( seats.clone().map( |who| view.hand_score( who ))),
+        array( seats.map( |who| view.game_score( who ))),
+        history( view ))
+}
+
+/// The request for the cards an AI script passes, in the view of the player, see `bot` module.
+pub fn request_pass( view: &PlayerView ) -> String {
+    format!( r#"{{"event":"request_pass",{}}}"#, self::view( view ))
+}
+
+/// The request for the card an AI script discards, in the view of the player, see `bot` module.
+pub fn request_play( view: &PlayerView ) -> String {
+    format!( r#"{{"event":"request_play",{}}}"#, self::view( view ))
+}
+
+/// The event telling an AI script that the last move was taken back, with the view of the player to resync with.
+pub fn undone( view: &PlayerView ) -> String {
+    format!( r#"{{"event":"undone",{}}}"#, self::view( view ))
+}
+
+/// Extracts the string value of the field from one line of JSON object, e.g. "2c" of `cards` in {"cards":"2c"}.
+///
+/// Only the escapes of quotes and backslashes are recognized.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::json;
+///
+/// assert_eq!( json::string_field( r#"{"cards": "2c 3c 4c"}"#, "cards" ), Some( "2c 3c 4c".to_owned() ));
+/// assert_eq!( json::string_field( r#"{"card":"2c"}"#, "cards" ), None );
+/// ```
+pub fn string_field( line: &str, key: &str ) -> Option<String> {
+    let rest = &line[ line.find( &string( key ))? + key.len() + 2.. ];
+    let mut chars = rest.trim_start().strip_prefix( ':' )?.trim_start().strip_prefix( '"' )?.chars();
+    let mut value = String::new();
+    while let Some( ch ) = chars.next() {
+        match ch {
+            '"'  => return Some( value ),
+            '\\' => value.push( chars.next()? ),
+            ch   => value.push( ch ),
+        }
+    }
+    None
+}
+
+/// The event of the times of winning the game for each player.
+pub fn statistics( awards: &[u32] ) -> String {
+    format!( r#"{{"event":"statistics","awards":{}}}"#, array( awards ))
+}
+
+impl Event {
+    /// Represents the event as one line of JSON, in the view of the given player.
+    ///
+    /// Cards in other players' hands are hidden, unless the viewer passes them or receives them.
+    /// The arrays of cards and scores have one item for each of the `seats` players.
+    pub fn to_json( &self, viewer: usize, seats: usize ) -> String {
+        match *self {
+            Event::GameStarted =>
+                r#"{"event":"game_started"}"#.to_owned(),
+            Event::Dealt{ deal, hands } =>
+                format!( r#"{{"event":"dealt","deal":{deal},"hand":{}}}"#, cards( hands[ viewer ])),
+            Event::Passed{ who, cards: passed } => if who == viewer {
+                format!( r#"{{"event":"passed","who":{who},"cards":{}}}"#, cards( passed ))
+            } else {
+                format!( r#"{{"event":"passed","who":{who}}}"# )
+            },
+            Event::Received{ who, from, cards: received } => if who == viewer || from == viewer {
+                format!( r#"{{"event":"received","who":{who},"from":{from},"cards":{}}}"#, cards( received ))
+            } else {
+                format!( r#"{{"event":"received","who":{who},"from":{from}}}"# )
+            },
+            Event::TrickStarted{ round, leader } =>
+                format!( r#"{{"event":"trick_started","round":{round},"leader":{leader}}}"# ),
+            Event::Discarded{ who, card } =>
+                format!( r#"{{"event":"card_played","who":{who},"card":{}}}"#, cards( card )),
+            Event::TrickWon{ trick, points } =>
+                format!( r#"{{"event":"trick_complete","round":{},"leader":{},"winner":{},"cards":{},"points":{points}}}"#,
+                    trick.round, trick.leader, trick.winner, array( trick.cards[ ..seats ].iter().map( |&card| cards( card )))),
+            Event::ShotTheMoon{ who, sun, rule } =>
+                format!( r#"{{"event":"shot_the_moon","who":{who},"sun":{sun},"rule":"{}"}}"#, moon_rule_name( rule )),
+            Event::HandScored{ hand_scores, game_scores } =>
+                format!( r#"{{"event":"scores","hand":{},"game":{}}}"#, array( &hand_scores[ ..seats ]), array( &game_scores[ ..seats ])),
+            Event::GameOver{ winner } =>
+                format!( r#"{{"event":"winner","winner":{winner}}}"# ),
+        }
+    }
+}
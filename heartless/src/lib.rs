@@ -25,17 +25,48 @@
 //! }
 //! ```
 
+pub mod bot;
+pub use bot::*;
+
+pub mod cancellation;
+pub use cancellation::*;
+
 pub mod card;
 pub use card::*;
 
+pub use fastrand::Rng;
+
 pub mod cli;
 
 pub mod game;
 pub use game::*;
 
+pub mod history;
+pub use history::*;
+
+pub mod ismcts;
+pub use ismcts::*;
+
+pub mod json;
+
+pub mod pimc;
+pub use pimc::*;
+
 pub mod player;
 pub use player::*;
 
+pub mod rule_set;
+pub use rule_set::*;
+
+pub mod solver;
+pub use solver::*;
+
+pub mod state;
+pub use state::*;
+
+pub mod view;
+pub use view::*;
+
 #[cfg( all( test, feature="altio" ))]
 mod tests {
     use crate::*;
@@ -44,18 +75,11 @@ mod tests {
     fn it_works() {
         let mut game = Game::with_config( cli::Config {
             ascii_suit      : true,
-            lowercase_rank  : false,
-            no_shared_suit  : false,
-            no_spaces       : false,
-            me              : None,
-            left            : None,
-            opposite        : None,
-            right           : None,
             seed            : Some( 2024 ),
             count           : Some( 1 ),
-            timeout         : None,
             impatient       : Some( true ),
             automatic       : true,
+            ..cli::Config::default()
         });
 
         let io = game.altio.clone();
@@ -64,4 +88,63 @@ mod tests {
         assert_eq!( io.recv(),     include_str!( "../output/out-seed_2024" ));
         assert_eq!( io.recv_err(), include_str!( "../output/err-seed_2024" ));
     }
+
+    #[test]
+    fn it_works_in_json() {
+        let mut game = Game::with_config( cli::Config {
+            ascii_suit      : true,
+            seed            : Some( 2024 ),
+            count           : Some( 1 ),
+            impatient       : Some( true ),
+            automatic       : true,
+            json            : true,
+            ..cli::Config::default()
+        });
+
+        let io = game.altio.clone();
+        game.main_loop();
+
+        assert_eq!( io.recv(),     include_str!( "../output/json-seed_2024" ));
+        assert_eq!( io.try_recv_err(), None );
+    }
+
+    #[test]
+    fn it_replays() {
+        let config = cli::Config {
+            ascii_suit      : true,
+            seed            : Some( 2024 ),
+            count           : Some( 1 ),
+            impatient       : Some( true ),
+            automatic       : true,
+            ..cli::Config::default()
+        };
+        let mut game = Game::with_config( config.clone() );
+        game.main_loop();
+
+        let path = std::env::temp_dir().join( "heartless-seed_2024.replay" );
+        game.history().write_to( &game, &path ).unwrap();
+        let ( rules, history ) = History::read_from( &path ).unwrap();
+        assert_eq!( history, *game.history() );
+        assert_eq!( rules.rule_set, game.rule_set );
+
+        let mut replay = Game::with_config( cli::Config{ replay: Some( path ), ..config });
+        let io = replay.altio.clone();
+        replay.main_loop();
+
+        assert_eq!( io.recv(), include_str!( "../output/replay-seed_2024" ));
+    }
+
+    #[test]
+    fn rejects_other_save_versions() {
+        let mut game = Game::with_config( cli::Config{ seed: Some( 2024 ), ..cli::Config::default() });
+        let path = std::env::temp_dir().join( "heartless-seed_2024.save" );
+        game.save_to( &path ).unwrap();
+        assert!( game.load_from( &path ).is_ok() );
+
+        let saved = std::fs::read_to_string( &path ).unwrap();
+        std::fs::write( &path, saved.replacen( &format!( "heartless-save {SAVE_VERSION}" ), "heartless-save 0", 1 )).unwrap();
+        assert!( matches!( game.load_from( &path ), Err( LoadError::Version{ found: 0, supported: SAVE_VERSION })));
+        std::fs::write( &path, "heartless\n" ).unwrap();
+        assert!( matches!( game.load_from( &path ), Err( LoadError::Header( _ ))));
+    }
 }
@@ -0,0 +1,190 @@
+//! Perfect Information Monte Carlo, the built-in strategy that samples the hidden hands.
+//!
+//! Each sample deals the cards not seen yet to the other players, consistently with the number of cards they hold,
+//! the suits they have shown void in and the cards passed to them. The sample is then solved by a double-dummy
+//! search, and the card with the fewest points expected over all the samples is discarded.
+
+use crate::*;
+use crate::solver::{Points, Position};
+
+use std::time::{Duration, Instant};
+
+/// The samples of hidden hands drawn for each card to discard, unless `Pimc::millis` runs out.
+pub const DEFAULT_PIMC_SAMPLES: usize = 20;
+
+/// How many tricks, including the current one, the double-dummy search looks ahead.
+pub const DEFAULT_PIMC_DEPTH: usize = 2;
+
+/// The double-dummy search looks ahead to the end of the hand once fewer tricks than this are left.
+const ENDGAME_TRICKS: usize = 5;
+
+/// The budget of the Perfect Information Monte Carlo strategy.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = GameState::default();
+/// state.pass_schedule = PassSchedule::NoPassing;
+/// state.apply( Action::Deal{ hands: Cards::deal_with( &mut Rng::with_seed( 2024 ))}).unwrap();
+///
+/// let pimc = Pimc{ samples: 4, ..Pimc::default() };
+/// let mut rng = Rng::with_seed( 2024 );
+/// while state.phase() == Phase::Discarding {
+///     let who = state.next_to_discard();
+///     let card = pimc.pick_card( &state, who, &mut rng );
+///     state.apply( Action::Discard{ who, card }).unwrap();
+/// }
+/// assert_eq!( state.players().map( |who| state.player( who ).taken.count() ).sum::<u8>(), 52 );
+/// ```
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub struct Pimc {
+    /// How many samples of hidden hands to draw.
+    pub samples : usize,
+    /// Stops drawing samples after this many milliseconds, if given, with at least one sample drawn.
+    pub millis  : Option<u64>,
+    /// How many tricks, including the current one, the double-dummy search looks ahead.
+    pub depth   : usize,
+}
+
+impl Default for Pimc {
+    fn default() -> Self {
+        Pimc{ samples: DEFAULT_PIMC_SAMPLES, millis: None, depth: DEFAULT_PIMC_DEPTH }
+    }
+}
+
+impl Pimc {
+    /// Picks the card for the player to discard, who must be the next to discard.
+    pub fn pick_card( &self, state: &GameState, who: usize, rng: &mut Rng ) -> Cards {
+        let candidates = state.legal_plays( who ).iter().collect::<Vec<_>>();
+        if candidates.len() <= 1 {
+            return candidates.first().copied().unwrap_or_default();
+        }
+
+        let started = Instant::now();
+        let horizon = if state.rounds() - state.round < ENDGAME_TRICKS {
+            state.rounds()
+        } else {
+            ( state.round + self.depth.max( 1 ) - 1 ).min( state.rounds() )
+        };
+        let mut search = Search::new( state, who, horizon );
+        let mut totals = vec![ 0; candidates.len() ];
+        for nth in 0..self.samples.max( 1 ) {
+            if nth > 0 && self.millis.is_some_and( |millis| started.elapsed() >= Duration::from_millis( millis )) {
+                break;
+            }
+            let position = Position::new( state, sample_hands( state, who, rng ));
+            let mut searched = ( NO_CARD, 0 );
+            for ( total, &card ) in totals.iter_mut().zip( &candidates ) {
+                let equivalent = position.move_of( card, state, &search.points ).expect( "every legal play is searched" );
+                if equivalent != searched.0 {
+                    searched = ( equivalent, search.after( &position, equivalent, i32::MIN, i32::MAX ));
+                }
+                *total += searched.1; // the same as the lower card if they are equivalent
+            }
+        }
+        let best = ( 0..candidates.len() ).min_by_key( |&i| totals[i] ).unwrap_or_default();
+        candidates[ best ]
+    }
+}
+
+/// Deals the cards not seen by the player to the others, who hold as many cards as they really do,
+/// without the suits they have shown void in, and with the cards passed to them by the player not discarded yet.
+pub(crate) fn sample_hands( state: &GameState, who: usize, rng: &mut Rng ) -> [Cards;MAX_PLAYERS] {
+    let mut hands = [NO_CARD;MAX_PLAYERS];
+    hands[ who ] = state.player( who ).hand;
+    if let Some( record ) = state.history().hands.last() {
+        let split = state.split_passing( who, record.passes[ who ]);
+        for i in state.players().filter( |&i| i != who ) {
+            hands[i] = split[i] - state.discarded;
+        }
+    }
+    let known = hands.iter().fold( NO_CARD, |cards, &hand| cards + hand );
+    let mut unseen = ( state.deck() - state.discarded - known ).iter().collect::<Vec<_>>();
+    let others = state.players().filter( |&i| i != who ).collect::<Vec<_>>();
+    let room = |hands: &[Cards;MAX_PLAYERS], i: usize| state.player(i).hand.count() > hands[i].count();
+
+    for attempt in 0..16 {
+        let mut dealt = hands;
+        rng.shuffle( &mut unseen );
+        let may_hold = |i: usize, card: Cards| attempt == 15 || state.player_may_have( i, card.suit() );
+        unseen.sort_by_key( |&card| others.iter().filter( |&&i| may_hold( i, card )).count() );
+        let complete = unseen.iter().all( |&card| {
+            let receivers = others.iter().copied().filter( |&i| may_hold( i, card ) && room( &dealt, i )).collect::<Vec<_>>();
+            if receivers.is_empty() {
+                return false;
+            }
+            dealt[ receivers[ rng.usize( ..receivers.len() )]] += card;
+            true
+        });
+        if complete {
+            return dealt;
+        }
+    }
+    hands
+}
+
+/// The double-dummy search, in which the team of the player minimizes the points it takes while the others
+/// maximize them, both knowing all the hands. Cards held at the horizon count a fraction of their points.
+struct Search<'a> {
+    state     : &'a GameState,
+    seats     : usize,
+    minimizer : [bool;MAX_PLAYERS],
+    penalty   : Cards,
+    points    : Points,
+    horizon   : usize,
+}
+
+impl<'a> Search<'a> {
+    fn new( state: &'a GameState, who: usize, horizon: usize ) -> Self {
+        let mut minimizer = [false;MAX_PLAYERS];
+        state.players().for_each( |i| minimizer[i] = state.team_of( i ) == state.team_of( who ));
+        Search {
+            state,
+            seats     : state.seats,
+            minimizer ,
+            penalty   : state.rule_set.penalty_cards(),
+            points    : Points::new( state ),
+            horizon   ,
+        }
+    }
+    /// The points the team takes, scaled by the number of players, after the next player discards the card.
+    fn after( &mut self, position: &Position, card: Cards, alpha: i32, beta: i32 ) -> i32 {
+        let mut next = *position;
+        if !next.play( card, self.state ) {
+            return self.value( &next, alpha, beta );
+        }
+
+        let cards = next.played();
+        let seats = self.seats;
+        let points = if self.minimizer[ next.winner ] { self.points.of( cards ) * seats as i32 } else { 0 };
+        if next.round >= self.horizon {
+            let held = ( ME..seats ).filter( |&i| self.minimizer[i] ).map( |i| self.points.of( next.hands[i] & self.penalty )).sum::<i32>();
+            return points + held;
+        }
+        next.next_trick();
+        points + self.value( &next, alpha.saturating_sub( points ), beta.saturating_sub( points ))
+    }
+    /// The points the team takes, scaled by the number of players, from the position on, by alpha-beta pruning.
+    fn value( &mut self, position: &Position, mut alpha: i32, mut beta: i32 ) -> i32 {
+        let minimizing = self.minimizer[ position.next ];
+        let mut best = if minimizing { i32::MAX } else { i32::MIN };
+        let moves = position.moves( self.state, &self.points );
+        for card in position.ordered( moves, &self.points ) {
+            let value = self.after( position, card, alpha, beta );
+            if minimizing {
+                best = best.min( value );
+                beta = beta.min( value );
+            } else {
+                best = best.max( value );
+                alpha = alpha.max( value );
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
@@ -7,10 +7,15 @@ pub const ME       : usize = 0;
 pub const LEFT     : usize = 1;
 pub const OPPOSITE : usize = 2;
 pub const RIGHT    : usize = 3;
-pub const NOBODY   : usize = 4;
+pub const NOBODY   : usize = 6;
 
-pub const PLAYERS  : Range<usize> = ME..NOBODY;
-pub const OTHERS   : Range<usize> = LEFT..NOBODY;
+/// The most players in one game, see `GameState::seats`.
+pub const MAX_PLAYERS : usize = NOBODY;
+
+/// The seats of the four-player game, see `GameState::players()` for games of other sizes.
+pub const PLAYERS  : Range<usize> = ME..4;
+/// The seats other than me in the four-player game, see `GameState::others()` for games of other sizes.
+pub const OTHERS   : Range<usize> = LEFT..4;
 
 /// The game player's definition
 #[derive( Clone, Copy, Default, Debug )]
@@ -19,10 +24,12 @@ pub struct Player {
     pub hand       : Cards,
     /// The card which the player is discarding
     pub discarding : Cards,
-    /// The score in this deal (up to 26)
-    pub hand_score : u8,
-    /// The score in this game (up to 99 or the game is over)
-    pub game_score : u8,
+    /// The cards taken in the tricks won in this deal
+    pub taken      : Cards,
+    /// The score in this deal (up to 26 in the standard rule pack, or -10 with the Jack of Diamonds)
+    pub hand_score : i32,
+    /// The score in this game, summed over all the deals
+    pub game_score : i32,
     /// Observing which suit this player's hand does not hold
     pub suit_bits  : u8,
     /// Times of winning the game
@@ -34,6 +41,7 @@ impl Player {
     pub fn reset( &mut self ) {
         self.hand       = NO_CARD;
         self.discarding = NO_CARD;
+        self.taken      = NO_CARD;
         self.hand_score = 0;
         self.game_score = 0;
         self.suit_bits  = NO_SUIT;
@@ -0,0 +1,160 @@
+//! Rule packs telling how many points each card counts.
+
+use crate::{
+    card::*,
+    game::Rule,
+};
+
+/// The well-known variants of Hearts, which differ in the points of cards.
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum )]
+pub enum RulePack {
+    /// Hearts count one point each, and the Queen of Spades counts 13 points.
+    #[default]
+    Standard,
+    /// The Ace of Spades counts 7 points and the King of Spades 10 points, besides the standard ones.
+    BlackMaria,
+    /// The Jack of Diamonds counts -10 points, besides the standard ones.
+    Omnibus,
+    /// Hearts count their face value, e.g. 11 for the Jack and 14 for the Ace.
+    SpotHearts,
+    /// The Queen of Hearts counts 13 points instead of one.
+    PinkLady,
+}
+
+/// The points of each card and the switchable rules of discarding, consulted by scoring, shooting the moon,
+/// rule checking and the built-in AI.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let black_maria = RuleSet::new( RulePack::BlackMaria, false );
+/// assert_eq!( black_maria.points_of( ACE_OF_SPADES + KING_OF_SPADES + QUEEN_OF_SPADES ), 30 );
+/// assert_eq!( black_maria.moon_points(), 43 );
+///
+/// let spot_hearts = RuleSet::new( RulePack::SpotHearts, true );
+/// assert_eq!( spot_hearts.points_of( ACE_OF_HEARTS + JACK_OF_DIAMONDS ), 4 );
+/// assert_eq!( spot_hearts.penalty_cards(), HEARTS + QUEEN_OF_SPADES );
+/// ```
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub struct RuleSet {
+    /// The rule pack which the points are based on.
+    pub pack             : RulePack,
+    /// Whether the Jack of Diamonds counts -10 points, always set for `RulePack::Omnibus`.
+    pub jack_of_diamonds : bool,
+    /// Hearts and other penalty cards may be discarded in the first trick.
+    pub points_on_first  : bool,
+    /// Discarding the Queen of Spades breaks hearts, as discarding a heart does.
+    pub queen_breaks     : bool,
+    /// Hearts may be led at any time, even before hearts are broken.
+    pub hearts_anytime   : bool,
+    /// The first trick may be led by any card of the opening card's suit, not only the opening card.
+    pub any_opening_suit : bool,
+        points           : [i32;52],
+        penalty          : Cards,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::new( RulePack::Standard, false )
+    }
+}
+
+impl RuleSet {
+    /// Constructs the rule set of the pack, with the Jack of Diamonds counting -10 points if `jack_of_diamonds` is set.
+    pub fn new( pack: RulePack, jack_of_diamonds: bool ) -> Self {
+        let jack_of_diamonds = jack_of_diamonds || pack == RulePack::Omnibus;
+        let mut points = [0;52];
+        for card in HEARTS.iter() {
+            points[ card.deck_index() ] = match pack {
+                RulePack::SpotHearts => card.suit_and_rank().1 as i32,
+                _                    => 1,
+            };
+        }
+        points[ QUEEN_OF_SPADES.deck_index() ] = 13;
+        match pack {
+            RulePack::BlackMaria => {
+                points[ ACE_OF_SPADES .deck_index() ] = 7;
+                points[ KING_OF_SPADES.deck_index() ] = 10;
+            },
+            RulePack::PinkLady   => points[ QUEEN_OF_HEARTS.deck_index() ] = 13,
+            _                    => (),
+        }
+        if jack_of_diamonds {
+            points[ JACK_OF_DIAMONDS.deck_index() ] = -10;
+        }
+        let penalty = CARDS.iter().filter( |card| points[ card.deck_index() ] > 0 ).fold( NO_CARD, |cards, &card| cards + card );
+        RuleSet {
+            pack             ,
+            jack_of_diamonds ,
+            points_on_first  : false,
+            queen_breaks     : false,
+            hearts_anytime   : false,
+            any_opening_suit : false,
+            points           ,
+            penalty          ,
+        }
+    }
+    /// The sum of points of the cards.
+    pub fn points_of( &self, cards: Cards ) -> i32 {
+        cards.iter().map( |card| self.points[ card.deck_index() ]).sum()
+    }
+    /// The cards counting positive points, all of which are taken to shoot the moon.
+    pub fn penalty_cards( &self ) -> Cards {
+        self.penalty
+    }
+    /// Checks the rules of discarding the card held in hand, and returns the broken one, if any.
+    ///
+    /// The `suit_mask` is the suit to follow, or `NO_CARD` for the leader of the trick, and `opening_card` is given
+    /// in the first trick only. Penalty cards may be discarded in the first trick if the player has no other choice,
+    /// e.g. the opening card itself, or the only cards held in the suit to follow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let rule_set = RuleSet::new( RulePack::BlackMaria, false );
+    /// let hand = ACE_OF_SPADES + QUEEN_OF_SPADES + TWO_OF_HEARTS;
+    /// assert_eq!( rule_set.break_the_rule( hand, QUEEN_OF_SPADES, CLUBS, Some( TWO_OF_CLUBS ), false ), None );
+    /// assert_eq!( rule_set.break_the_rule( hand + TWO_OF_SPADES, QUEEN_OF_SPADES, CLUBS, Some( TWO_OF_CLUBS ), false ), Some( Rule::QueenInFirstRound ));
+    /// assert_eq!( rule_set.break_the_rule( hand + TWO_OF_CLUBS, QUEEN_OF_SPADES, SPADES, Some( TWO_OF_SPADES ), false ), None );
+    /// assert_eq!( rule_set.break_the_rule( hand + TWO_OF_CLUBS, TWO_OF_HEARTS, NO_CARD, Some( TWO_OF_HEARTS ), false ), None );
+    /// assert_eq!( rule_set.break_the_rule( hand, TWO_OF_HEARTS, NO_CARD, None, false ), Some( Rule::Heartbreak ));
+    /// ```
+    pub fn break_the_rule( &self, hand: Cards, card: Cards, suit_mask: Cards, opening_card: Option<Cards>, hearts_may_lead: bool ) -> Option<Rule> {
+        let leading = suit_mask == NO_CARD;
+        let opening = opening_card.filter( |&opening_card| leading && hand.contains( opening_card ));
+        let choices = match opening {
+            Some( opening_card ) if self.any_opening_suit       => hand & opening_card.suit_mask(),
+            Some( opening_card )                                => opening_card,
+            None if !leading && hand.contains_suit( suit_mask ) => hand & suit_mask,
+            None                                                => hand,
+        };
+        let points_allowed = opening_card.is_none() || self.points_on_first || choices.are_all_of( self.penalty );
+        if let Some( opening_card ) = opening {
+            let opening = if self.any_opening_suit { card.contains_suit( opening_card.suit_mask() )} else { card == opening_card };
+            if !opening {
+                return Some( Rule::TwoOfClubs );
+            }
+        }
+        if !leading && hand.contains_suit( suit_mask ) && !card.contains_suit( suit_mask ) {
+            Some( Rule::FollowSuit )
+        } else if card.is_heart() && !choices.are_all_of( HEARTS ) {
+            if leading {
+                ( !hearts_may_lead ).then_some( Rule::Heartbreak )
+            } else {
+                ( !points_allowed ).then_some( Rule::HeartInFirstRound )
+            }
+        } else if card & ( self.penalty - HEARTS ) != NO_CARD && !points_allowed {
+            Some( Rule::QueenInFirstRound )
+        } else {
+            None
+        }
+    }
+    /// The points of all the penalty cards, which shooting the moon scores, e.g. 26 for `RulePack::Standard`.
+    pub fn moon_points( &self ) -> i32 {
+        self.points_of( self.penalty_cards() )
+    }
+}
@@ -0,0 +1,336 @@
+//! The exact double-dummy solver, for positions with all the hands known, e.g. in replays.
+//!
+//! The solver tells the fewest points each player can be forced to take from the current trick onwards, assuming
+//! all the other players discard to make the player take the most points. It searches to the end of the hand by
+//! alpha-beta pruning, with the positions at the beginning of tricks kept in a transposition table keyed by the
+//! hands in their `Cards` representation. Shooting the moon is not considered.
+
+use crate::*;
+
+use std::collections::HashMap;
+
+/// How many tricks at the end of each hand the "analyze" command solves by default.
+pub const DEFAULT_ANALYZE_TRICKS: usize = 6;
+
+/// The position at the beginning of a trick, for whom it is solved: the hands, the leader and whether hearts
+/// may be led.
+type Key = ( usize, [u64;MAX_PLAYERS], usize, bool );
+
+/// The most positions kept by the solver, which forgets them all once exceeded.
+const MAX_POSITIONS: usize = 1 << 20;
+
+/// The exact double-dummy solver, which keeps what it has solved to solve later positions faster.
+///
+/// The positions solved are valid in the same game, or in the games with the same rules. The time grows fast with
+/// the tricks left, so it is meant for end-game positions, e.g. the last six tricks, see `DEFAULT_ANALYZE_TRICKS`.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = GameState::default();
+/// state.pass_schedule = PassSchedule::NoPassing;
+/// state.apply( Action::Deal{ hands: Cards::deal_with( &mut Rng::with_seed( 2024 ))}).unwrap();
+/// let pimc = Pimc{ samples: 1, ..Pimc::default() };
+/// let mut rng = Rng::with_seed( 2024 );
+/// while state.round < 9 {
+///     let who = state.next_to_discard();
+///     state.apply( Action::Discard{ who, card: pimc.pick_card( &state, who, &mut rng )}).unwrap();
+/// }
+///
+/// let mut solver = Solver::default();
+/// let forced = state.players().map( |who| solver.forced_points( &state, who )).collect::<Vec<_>>();
+/// let left = state.deck() - state.discarded;
+/// assert!( forced.iter().all( |&points| points <= state.points_of( left )));
+///
+/// let line = solver.optimal_line( &state, ME );
+/// assert_eq!( line.len(), left.count() as usize );
+/// for ( who, card ) in line {
+///     state.apply( Action::Discard{ who, card }).unwrap();
+/// }
+/// ```
+#[derive( Debug, Default )]
+pub struct Solver {
+    table : HashMap<Key,( i32, i32 )>,
+}
+
+impl Solver {
+    /// The fewest points the player can be forced to take from the current trick onwards,
+    /// including the cards already discarded in it, or 0 if the hand is not being discarded.
+    pub fn forced_points( &mut self, state: &GameState, who: usize ) -> i32 {
+        if state.phase() != Phase::Discarding {
+            return 0;
+        }
+        let position = Position::of( state );
+        Solving::new( self, state, who ).value( &position, i32::MIN, i32::MAX )
+    }
+    /// The fewest points the player can be forced to take, after the next player discards each card allowed.
+    pub fn card_values( &mut self, state: &GameState, who: usize ) -> Vec<( Cards, i32 )> {
+        if state.phase() != Phase::Discarding {
+            return Vec::new();
+        }
+        Solving::new( self, state, who ).values( &Position::of( state ))
+    }
+    /// The cards discarded by each player to the end of the hand, when the player takes the fewest points
+    /// and the others make the player take the most, lower cards first among the equally good ones.
+    pub fn optimal_line( &mut self, state: &GameState, who: usize ) -> Vec<( usize, Cards )> {
+        let mut line = Vec::new();
+        if state.phase() != Phase::Discarding {
+            return line;
+        }
+        let mut solving = Solving::new( self, state, who );
+        let mut position = Position::of( state );
+        loop {
+            let values = solving.values( &position );
+            let best = if position.next == who {
+                values.iter().min_by_key( |&&( _, value )| value )
+            } else {
+                values.iter().rev().max_by_key( |&&( _, value )| value )
+            };
+            let Some( &( card, _ )) = best else { break };
+            line.push(( position.next, card ));
+            if position.play( card, state ) {
+                if position.round >= state.rounds() {
+                    break;
+                }
+                position.next_trick();
+            }
+        }
+        line
+    }
+}
+
+/// The search for one player, see `Solver`.
+struct Solving<'a> {
+    table   : &'a mut HashMap<Key,( i32, i32 )>,
+    state   : &'a GameState,
+    who     : usize,
+    points  : Points,
+}
+
+impl<'a> Solving<'a> {
+    fn new( solver: &'a mut Solver, state: &'a GameState, who: usize ) -> Self {
+        Solving {
+            table   : &mut solver.table,
+            state   ,
+            who     ,
+            points  : Points::new( state ),
+        }
+    }
+    fn values( &mut self, position: &Position ) -> Vec<( Cards, i32 )> {
+        position.legal( self.state ).iter()
+            .map( |card| ( card, self.after( position, card, i32::MIN, i32::MAX )))
+            .collect()
+    }
+    /// The points the player takes after the next player discards the card.
+    fn after( &mut self, position: &Position, card: Cards, alpha: i32, beta: i32 ) -> i32 {
+        let mut next = *position;
+        if !next.play( card, self.state ) {
+            return self.value( &next, alpha, beta );
+        }
+        let points = if next.winner == self.who { self.points.of( next.played() ) } else { 0 };
+        if next.round >= self.state.rounds() {
+            return points;
+        }
+        next.next_trick();
+        points + self.value( &next, alpha.saturating_sub( points ), beta.saturating_sub( points ))
+    }
+    /// The points the player takes from the position on, by alpha-beta pruning.
+    fn value( &mut self, position: &Position, mut alpha: i32, mut beta: i32 ) -> i32 {
+        let key = ( position.played() == NO_CARD ).then( ||
+            ( self.who, position.hands.map( |hand| hand.0 ), position.leader, position.broken ));
+        if let Some( &( lower, upper )) = key.as_ref().and_then( |key| self.table.get( key )) {
+            if lower == upper || lower >= beta {
+                return lower;
+            }
+            if upper <= alpha {
+                return upper;
+            }
+            alpha = alpha.max( lower );
+            beta = beta.min( upper );
+        }
+
+        let ( lowest, highest ) = ( alpha, beta );
+        let minimizing = position.next == self.who;
+        let mut best = if minimizing { i32::MAX } else { i32::MIN };
+        let moves = position.moves( self.state, &self.points );
+        for card in position.ordered( moves, &self.points ) {
+            let value = self.after( position, card, alpha, beta );
+            if minimizing {
+                best = best.min( value );
+                beta = beta.min( value );
+            } else {
+                best = best.max( value );
+                alpha = alpha.max( value );
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        if let Some( key ) = key {
+            let bounds = if best <= lowest {
+                ( i32::MIN, best )
+            } else if best >= highest {
+                ( best, i32::MAX )
+            } else {
+                ( best, best )
+            };
+            if self.table.len() >= MAX_POSITIONS {
+                self.table.clear();
+            }
+            self.table.insert( key, bounds );
+        }
+        best
+    }
+}
+
+/// The points of each card, indexed by its bit in the `Cards` representation.
+pub(crate) struct Points( [i32;64] );
+
+impl Points {
+    pub(crate) fn new( state: &GameState ) -> Self {
+        let mut points = [0;64];
+        CARDS.iter().for_each( |&card| points[ card.0.trailing_zeros() as usize ] = state.points_of( card ));
+        Points( points )
+    }
+    pub(crate) fn of( &self, cards: Cards ) -> i32 {
+        let mut bits = cards.0;
+        let mut points = 0;
+        while bits != 0 {
+            points += self.0[ bits.trailing_zeros() as usize ];
+            bits &= bits - 1;
+        }
+        points
+    }
+}
+
+/// A position of the hand with all the cards known, in the middle of a trick or at its beginning.
+#[derive( Copy, Clone )]
+pub(crate) struct Position {
+    pub(crate) hands     : [Cards;MAX_PLAYERS],
+    pub(crate) trick     : [Cards;MAX_PLAYERS],
+    pub(crate) round     : usize,
+    pub(crate) leader    : usize,
+    pub(crate) next      : usize,
+    pub(crate) high_card : Cards,
+    pub(crate) winner    : usize,
+    pub(crate) broken    : bool,
+}
+
+impl Position {
+    /// The position of the state, with the hands sampled or known.
+    pub(crate) fn new( state: &GameState, hands: [Cards;MAX_PLAYERS] ) -> Self {
+        let trick = state.trick();
+        Position {
+            hands     ,
+            trick     : trick.cards,
+            round     : state.round,
+            leader    : trick.leader,
+            next      : state.next_to_discard(),
+            high_card : state.high_card,
+            winner    : trick.winner,
+            broken    : state.hearts_may_lead(),
+        }
+    }
+    /// The position of the state, with the hands known in the state.
+    pub(crate) fn of( state: &GameState ) -> Self {
+        let mut hands = [NO_CARD;MAX_PLAYERS];
+        state.players().for_each( |i| hands[i] = state.player(i).hand );
+        Position::new( state, hands )
+    }
+    /// The cards discarded in the trick.
+    pub(crate) fn played( &self ) -> Cards {
+        self.trick.iter().fold( NO_CARD, |cards, &card| cards + card )
+    }
+    /// The cards the next player may discard, see `RuleSet::break_the_rule()`.
+    pub(crate) fn legal( &self, state: &GameState ) -> Cards {
+        let hand = self.hands[ self.next ];
+        let suit_mask = if self.next == self.leader { NO_CARD } else { self.trick[ self.leader ].suit_mask() };
+        let opening_card = ( self.round == 1 ).then_some( state.opening_card );
+        hand.iter()
+            .filter( |&card| state.rule_set.break_the_rule( hand, card, suit_mask, opening_card, self.broken ).is_none() )
+            .fold( NO_CARD, |legal, card| legal + card )
+    }
+    /// Discards the card by the next player, and returns true if the trick is complete, see `Position::next_trick()`.
+    pub(crate) fn play( &mut self, card: Cards, state: &GameState ) -> bool {
+        let who = self.next;
+        self.hands[ who ] = self.hands[ who ] - card;
+        self.trick[ who ] = card;
+        self.broken |= card.is_heart() || ( state.rule_set.queen_breaks && card == QUEEN_OF_SPADES );
+        if card & self.trick[ self.leader ].suit_mask() != NO_CARD && card > self.high_card {
+            self.high_card = card;
+            self.winner = who;
+        }
+        self.next = ( who+1 ) % state.seats;
+        self.next == self.leader
+    }
+    /// Starts the next trick, led by the winner of the complete one.
+    pub(crate) fn next_trick( &mut self ) {
+        self.round += 1;
+        self.leader = self.winner;
+        self.next = self.winner;
+        self.trick = [NO_CARD;MAX_PLAYERS];
+        self.high_card = NO_CARD;
+    }
+    /// The cards the next player may discard, without the ones equivalent to a lower card of the same points,
+    /// that is, no card between them is held by the others or discarded in the trick.
+    pub(crate) fn moves( &self, state: &GameState, points: &Points ) -> Cards {
+        let hand = self.hands[ self.next ];
+        let legal = self.legal( state );
+        let others = self.hands.iter().fold( self.played(), |cards, &hand| cards + hand ) - hand;
+        let mut moves = NO_CARD;
+        let mut lower = 0_u64;
+        let mut bits = legal.0;
+        while bits != 0 {
+            let card = bits & bits.wrapping_neg();
+            let between = ( card - 1 ) & !( ( lower << 1 ).wrapping_sub( 1 ));
+            let equivalent = lower != 0 && Cards( lower ).suit_mask() == Cards( card ).suit_mask() && others.0 & between == 0
+                && points.0[ lower.trailing_zeros() as usize ] == points.0[ card.trailing_zeros() as usize ];
+            if !equivalent {
+                moves += Cards( card );
+            }
+            lower = card;
+            bits &= bits - 1;
+        }
+        moves
+    }
+    /// The move of `Position::moves()` which the legal card is equivalent to, i.e. the highest one not above it
+    /// in its suit, or `None` if the card may not be discarded.
+    pub(crate) fn move_of( &self, card: Cards, state: &GameState, points: &Points ) -> Option<Cards> {
+        if !self.legal( state ).contains( card ) {
+            return None;
+        }
+        ( self.moves( state, points ) & card.suit_mask() ).iter().rev().find( |&lower| lower <= card )
+    }
+    /// Orders the moves to try the likely best ones first, which prunes more: the highest card not winning the trick,
+    /// the highest points when void, and the lowest card when leading.
+    pub(crate) fn ordered( &self, moves: Cards, points: &Points ) -> impl Iterator<Item=Cards> {
+        let mut keyed = [( 0, NO_CARD );20];
+        let mut len = 0;
+        let suit_mask = self.trick[ self.leader ].suit_mask();
+        let leading = self.next == self.leader;
+        let mut bits = moves.0;
+        while bits != 0 {
+            let card = Cards( bits & bits.wrapping_neg() );
+            let rank = card.0.trailing_zeros() as i32 % 16;
+            let points = points.0[ card.0.trailing_zeros() as usize ];
+            let key = if leading {
+                points*16 + rank
+            } else if card & suit_mask == NO_CARD {
+                -( points*16 + rank )
+            } else if card < self.high_card {
+                -rank
+            } else {
+                16 + rank
+            };
+            if len < keyed.len() {
+                keyed[ len ] = ( key, card );
+                len += 1;
+            }
+            bits &= bits - 1;
+        }
+        keyed[ ..len ].sort_unstable_by_key( |&( key, _ )| key );
+        keyed.into_iter().take( len ).map( |( _, card )| card )
+    }
+}
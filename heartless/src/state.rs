@@ -0,0 +1,1232 @@
+//! The step-driven game state machine, free of input, output and AI strategies.
+//!
+//! A `GameState` only changes by applying `Action`s, each of which is checked
+//! against the game rules and results in a sequence of `Event`s describing what
+//! happened. Consoles, GUIs, servers and tests can drive games with their own loops.
+
+use crate::*;
+
+use std::ops::Range;
+
+/// The actions that drive the game state.
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub enum Action {
+    /// Starts a new game. The scores are cleared while the times of winning are kept.
+    NewGame,
+    /// Deals cards to each player, indexed by seats, e.g. `ME`, `LEFT`, `OPPOSITE`, `RIGHT` in the four-player game.
+    Deal{ hands: [Cards;MAX_PLAYERS] },
+    /// The player passes three cards.
+    Pass{ who: usize, cards: Cards },
+    /// The player discards one card.
+    Discard{ who: usize, card: Cards },
+}
+
+/// The events resulting from applying actions.
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub enum Event {
+    /// A new game has started.
+    GameStarted,
+    /// The `deal`th hand of the game has been dealt.
+    Dealt{ deal: usize, hands: [Cards;MAX_PLAYERS] },
+    /// The player has chosen the cards to pass.
+    Passed{ who: usize, cards: Cards },
+    /// All players have passed, and the player received cards from another player, once for each player passing to it.
+    Received{ who: usize, from: usize, cards: Cards },
+    /// A new round is started by the early hand.
+    TrickStarted{ round: usize, leader: usize },
+    /// The player has discarded a card.
+    Discarded{ who: usize, card: Cards },
+    /// All the players have discarded in this round, and the winner took `points`.
+    TrickWon{ trick: Trick, points: i32 },
+    /// The player has taken all the hearts and the Queen of Spades, or all the tricks if `sun` is set,
+    /// and the hand scores have been settled as `rule` tells, which is never `MoonRule::ShooterChooses`.
+    ShotTheMoon{ who: usize, sun: bool, rule: MoonRule },
+    /// All the rounds of this hand have been played.
+    HandScored{ hand_scores: [i32;MAX_PLAYERS], game_scores: [i32;MAX_PLAYERS] },
+    /// The game is over as `Ending` tells, and the player or team with the lowest score wins.
+    GameOver{ winner: usize },
+}
+
+/// The step which the game state is waiting for.
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub enum Phase {
+    /// Waiting for `Action::Deal`.
+    Dealing,
+    /// Waiting for `Action::Pass` of the players who have not passed yet.
+    Passing,
+    /// Waiting for `Action::Discard` of `GameState::next_to_discard()`.
+    Discarding,
+    /// Waiting for `Action::NewGame`.
+    GameOver,
+}
+
+/// How to choose the winner among the players sharing the lowest score at the end of the game.
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum )]
+pub enum TieBreak {
+    /// Plays more hands until the tie is broken.
+    #[default]
+    PlayOn,
+    /// The tied player with the lowest score in the last hand wins, or plays on if still tied.
+    LastHand,
+    /// The tied player first in seat order wins, i.e. `ME`, `LEFT`, `OPPOSITE`, `RIGHT`.
+    FirstSeat,
+}
+
+/// To whom the players pass cards in each hand.
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum )]
+pub enum PassSchedule {
+    /// Passes to the left, the right, across, and holds every 4th hand. With other numbers of players, passes to
+    /// the left, the right, the 2nd left, the 2nd right and so on, and holds every hand of the number of players.
+    #[default]
+    LeftRightAcrossHold,
+    /// Passes to the left and the right in turn.
+    LeftRight,
+    /// Always passes to the left.
+    AlwaysLeft,
+    /// Never passes cards.
+    NoPassing,
+    /// Passes one card to each opponent, the lowest to the left, the middle across and the highest to the right.
+    /// Only for the four-player game.
+    Scatter,
+}
+
+/// How to score the hand when a player shoots the moon, i.e. takes all the penalty cards, see `RuleSet::penalty_cards()`.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = GameState::default();
+/// state.moon_rule = MoonRule::SubtractFromSelf;
+/// state.shoot_the_sun = true;
+/// state.read_line( "deal 3" ).unwrap(); // no passing in the 4th hand
+/// state.apply( Action::Deal{ hands: [ CLUBS, DIAMONDS, SPADES, HEARTS, NO_CARD, NO_CARD ]}).unwrap();
+///
+/// let mut events = Vec::new();
+/// while state.phase() == Phase::Discarding {
+///     let who = state.next_to_discard();
+///     let card = state.player( who ).hand.iter().next().unwrap();
+///     events.extend( state.apply( Action::Discard{ who, card }).unwrap() );
+/// }
+/// let shots = events.into_iter().filter( |event| matches!( event, Event::ShotTheMoon{..} )).collect::<Vec<_>>();
+/// assert_eq!( shots, vec![ Event::ShotTheMoon{ who: ME, sun: true, rule: MoonRule::SubtractFromSelf }]);
+/// assert_eq!( state.player( ME ).game_score, -52 );
+/// ```
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum )]
+pub enum MoonRule {
+    /// Each other player scores the points of all the penalty cards, e.g. additional 26 points, and the shooter scores zero.
+    #[default]
+    AddToOthers,
+    /// The shooter scores minus the points of all the penalty cards, e.g. -26 points, and the other players score zero.
+    SubtractFromSelf,
+    /// Adds to others unless doing so ends the game with the shooter not having the lowest score.
+    ShooterChooses,
+}
+
+/// Whether partners sitting opposite play as teams, i.e. `ME`+`OPPOSITE` against `LEFT`+`RIGHT` in the four-player game.
+///
+/// The partners pool their hand scores, so that they always share the same game score, and the winning team is
+/// represented by its first seat, e.g. `ME` or `LEFT`.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = GameState::default();
+/// assert_eq!( state.partner( LEFT ), NOBODY );
+/// state.partnership = Partnership::SoloMoon;
+/// assert_eq!( state.partner( LEFT ), RIGHT );
+/// assert_eq!( state.team_of( OPPOSITE ), ME );
+/// ```
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum )]
+pub enum Partnership {
+    /// Every player plays alone.
+    #[default]
+    Off,
+    /// Partners play as a team, and one player shoots the moon by taking all the penalty cards alone.
+    SoloMoon,
+    /// Partners play as a team, and shoot the moon by taking all the penalty cards between them.
+    JointMoon,
+}
+
+/// When the game is over and who wins it.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let game = Game::with_config( cli::Config::from_args([ "heartless", "--hands", "4", "--tie-break", "first-seat" ]));
+/// assert_eq!( game.ending, Ending{ target_score: 100, hands: Some(4), tie_break: TieBreak::FirstSeat });
+/// ```
+#[derive( Copy, Clone, Debug, PartialEq, Eq )]
+pub struct Ending {
+    /// The game is over when some player hits this score or higher.
+    pub target_score : i32,
+    /// The game is over after this number of hands instead, if given.
+    pub hands        : Option<usize>,
+    /// How to break ties for the lowest score.
+    pub tie_break    : TieBreak,
+}
+
+impl Default for Ending {
+    fn default() -> Self {
+        Ending {
+            target_score : 100,
+            hands        : None,
+            tie_break    : TieBreak::PlayOn,
+        }
+    }
+}
+
+impl Ending {
+    /// Returns true if the game is over after the `deal`th hand with the game scores of the players.
+    pub fn is_over( &self, deal: usize, game_scores: &[i32] ) -> bool {
+        match self.hands {
+            Some( hands ) => deal >= hands,
+            None          => game_scores.iter().any( |&score| score >= self.target_score ),
+        }
+    }
+    /// The winner with the lowest game score after the `deal`th hand, out of the players given as
+    /// `( who, game_score, hand_score )`, or `None` if the game is not over or the tie is not broken.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut ending = Ending{ target_score: 100, hands: None, tie_break: TieBreak::LastHand };
+    /// let scores = [( 0, 90, 20 ), ( 1, 104, 0 ), ( 2, 90, 10 )];
+    /// assert_eq!( ending.winner_of( 5, &scores ), Some( 2 ));
+    /// ending.tie_break = TieBreak::PlayOn;
+    /// assert_eq!( ending.winner_of( 5, &scores ), None );
+    /// ```
+    pub fn winner_of( &self, deal: usize, scores: &[( usize, i32, i32 )] ) -> Option<usize> {
+        if !self.is_over( deal, &scores.iter().map( |&( _, game_score, _ )| game_score ).collect::<Vec<_>>() ) {
+            return None;
+        }
+        let min_score = scores.iter().map( |&( _, game_score, _ )| game_score ).min()?;
+        let mut lowest = scores.iter().filter( |&&( _, game_score, _ )| game_score == min_score ).collect::<Vec<_>>();
+        match self.tie_break {
+            TieBreak::PlayOn    => (),
+            TieBreak::LastHand  => {
+                let min_hand_score = lowest.iter().map( |&&( _, _, hand_score )| hand_score ).min()?;
+                lowest.retain( |&&( _, _, hand_score )| hand_score == min_hand_score );
+            },
+            TieBreak::FirstSeat => lowest.truncate( 1 ),
+        }
+        match lowest[..] {
+            [ &( winner, _, _ )] => Some( winner ),
+            _                    => None,
+        }
+    }
+}
+
+/// The cards discarded in one round.
+#[derive( Copy, Clone, Debug, Default, PartialEq, Eq )]
+pub struct Trick {
+    /// Which round it is, 1~13 in the four-player game.
+    pub round  : usize,
+    /// Who discarded the first card in this round.
+    pub leader : usize,
+    /// Whose card is the highest card in this round.
+    pub winner : usize,
+    /// The card discarded by each player, or `NO_CARD` if not discarded yet.
+    pub cards  : [Cards;MAX_PLAYERS],
+}
+
+/// The game state of heartless, driven by `GameState::apply()`.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut state = GameState::default();
+/// let hands = Cards::deal();
+/// let events = state.apply( Action::Deal{ hands }).unwrap();
+/// assert_eq!( events, vec![ Event::Dealt{ deal: 1, hands }]);
+/// assert_eq!( state.phase(), Phase::Passing );
+///
+/// for who in PLAYERS {
+///     let cards = state.player( who ).hand.pick_three_cards().dest;
+///     state.apply( Action::Pass{ who, cards }).unwrap();
+/// }
+/// assert_eq!( state.phase(), Phase::Discarding );
+///
+/// let who = state.next_to_discard();
+/// let hand = state.player( who ).hand;
+/// assert!( hand.contains( TWO_OF_CLUBS ));
+/// let card = ( hand - TWO_OF_CLUBS ).iter().next().unwrap();
+/// assert_eq!( state.apply( Action::Discard{ who, card }), Err( Rule::TwoOfClubs ));
+/// ```
+#[derive( Clone, Debug )]
+pub struct GameState {
+        players         : [Player;MAX_PLAYERS],
+        winner          : usize,
+        deal            : usize,
+        phase           : Phase,
+        passing         : [Cards;MAX_PLAYERS],
+    pub discarded       : Cards,
+    pub suit_mask       : Cards,
+    pub round           : usize,
+    pub under_the_gun   : usize,
+    pub round_winner    : usize,
+    pub high_card       : Cards,
+    pub ending          : Ending,
+    pub rule_set        : RuleSet,
+    pub moon_rule       : MoonRule,
+    pub shoot_the_sun   : bool,
+    pub pass_schedule   : PassSchedule,
+    pub seats           : usize,
+    pub opening_card    : Cards,
+    pub partnership     : Partnership,
+        history         : History,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        GameState {
+            players         : <[Player;MAX_PLAYERS]>::default(),
+            winner          : NOBODY,
+            deal            : 0_usize,
+            phase           : Phase::Dealing,
+            passing         : [NO_CARD;MAX_PLAYERS],
+            discarded       : NO_CARD,
+            suit_mask       : NO_CARD,
+            round           : 0_usize,
+            under_the_gun   : NOBODY,
+            round_winner    : NOBODY,
+            high_card       : NO_CARD,
+            ending          : Ending::default(),
+            rule_set        : RuleSet::default(),
+            moon_rule       : MoonRule::AddToOthers,
+            shoot_the_sun   : false,
+            pass_schedule   : PassSchedule::LeftRightAcrossHold,
+            seats           : 4,
+            opening_card    : TWO_OF_CLUBS,
+            partnership     : Partnership::Off,
+            history         : History::default(),
+        }
+    }
+}
+
+impl GameState {
+    /// Constructs a game state which ends as the given `Ending` tells.
+    pub fn with_ending( ending: Ending ) -> Self {
+        GameState{ ending, ..GameState::default() }
+    }
+    /// The record of the game so far.
+    pub fn history( &self ) -> &History {
+        &self.history
+    }
+    /// The player at the given seat.
+    pub fn player( &self, who: usize ) -> &Player {
+        &self.players[ who ]
+    }
+    /// The winner of the game, or `NOBODY` if the game is not over.
+    pub fn winner( &self ) -> usize {
+        self.winner
+    }
+    /// How many hands have been dealt in this game.
+    pub fn deal( &self ) -> usize {
+        self.deal
+    }
+    /// The seats of the players in this game, from `ME` to `seats`-1.
+    pub fn players( &self ) -> Range<usize> {
+        ME..self.seats
+    }
+    /// The seats of the players other than me in this game.
+    pub fn others( &self ) -> Range<usize> {
+        LEFT..self.seats
+    }
+    /// The partner sitting opposite to the player, or `NOBODY` if players play alone.
+    pub fn partner( &self, who: usize ) -> usize {
+        if self.partnership == Partnership::Off || !self.seats.is_multiple_of( 2 ) {
+            NOBODY
+        } else {
+            ( who + self.seats/2 ) % self.seats
+        }
+    }
+    /// The first seat of the player's team, which is the player itself if players play alone.
+    pub fn team_of( &self, who: usize ) -> usize {
+        who.min( self.partner( who ))
+    }
+    /// The cards taken by the player, and also by its partner if partners shoot the moon jointly.
+    fn taken_for_moon( &self, who: usize ) -> Cards {
+        self.moon_taken( who, &self.players.map( |player| player.taken ))
+    }
+    /// The cards taken by the player out of the cards `taken` by each player, and also by its partner
+    /// if partners shoot the moon jointly, see `GameState::taken_for_moon()`.
+    pub(crate) fn moon_taken( &self, who: usize, taken: &[Cards;MAX_PLAYERS] ) -> Cards {
+        let partner = self.partner( who );
+        if self.partnership == Partnership::JointMoon && partner != NOBODY {
+            taken[ who ] + taken[ partner ]
+        } else {
+            taken[ who ]
+        }
+    }
+    /// Adds the hand scores of partners together if partners play as teams.
+    fn pool( &self, hand_scores: &[i32] ) -> Vec<i32> {
+        self.players().map( |i| match self.partner( i ) {
+            NOBODY  => hand_scores[i],
+            partner => hand_scores[i] + hand_scores[ partner ],
+        }).collect()
+    }
+    /// The cards dealt in each hand, see `Cards::deck_for()`.
+    pub fn deck( &self ) -> Cards {
+        Cards::deck_for( self.seats )
+    }
+    /// How many rounds, i.e. tricks, are played in each hand.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut state = GameState::default();
+    /// assert_eq!( state.rounds(), 13 );
+    /// state.seats = 3;
+    /// assert_eq!( state.rounds(), 17 );
+    /// state.seats = 5;
+    /// assert_eq!( state.rounds(), 10 );
+    /// ```
+    pub fn rounds( &self ) -> usize {
+        self.deck().count() as usize / self.seats
+    }
+    /// The player who has shot the moon in this hand, alone or with the partner, and whether it is a sun, if any.
+    pub fn moon_shooter( &self ) -> Option<(usize,bool)> {
+        let penalty_cards = self.rule_set.penalty_cards();
+        self.players().find( |&who| self.taken_for_moon( who ) & penalty_cards == penalty_cards ).map( |who|
+            ( who, self.shoot_the_sun && self.taken_for_moon( who ) == self.deck() ))
+    }
+    /// The step which this state is waiting for.
+    pub fn phase( &self ) -> Phase {
+        self.phase
+    }
+    /// Returns true if the player has passed cards in this hand.
+    pub fn has_passed( &self, who: usize ) -> bool {
+        self.passing[ who ] != NO_CARD
+    }
+    /// Who is going to discard a card.
+    pub fn next_to_discard( &self ) -> usize {
+        let discarded = self.players().filter( |&who| self.players[who].discarding != NO_CARD ).count();
+        ( self.under_the_gun + discarded ) % self.seats
+    }
+    /// The cards discarded so far in current round.
+    pub fn trick( &self ) -> Trick {
+        Trick {
+            round  : self.round,
+            leader : self.under_the_gun,
+            winner : self.round_winner,
+            cards  : self.players.map( |player| player.discarding ),
+        }
+    }
+    /// Returns true if this player's hand may hold cards of the given suit,
+    /// otherwise returns false.
+    pub fn player_may_have( &self, who: usize, suit: u8 ) -> bool {
+        self.players[who].may_have( suit )
+    }
+    /// Returns false if no cards are passed in this hand, e.g. every 4th hand.
+    pub fn needs_pass_cards( &self ) -> bool {
+        match self.pass_schedule {
+            PassSchedule::LeftRightAcrossHold => !self.deal.is_multiple_of( self.seats ),
+            PassSchedule::NoPassing           => false,
+            _                                 => true,
+        }
+    }
+    /// To whom the player passes cards in this hand, or the player itself if no cards are passed
+    /// or they are scattered, see `GameState::pass_receivers()`.
+    pub fn pass_to( &self, who: usize ) -> usize {
+        let offset = match self.pass_schedule {
+            PassSchedule::LeftRightAcrossHold => match self.deal % self.seats {
+                0                  => 0,
+                nth if nth%2 == 1  => nth.div_ceil( 2 ),
+                nth                => self.seats - nth/2,
+            },
+            PassSchedule::LeftRight           => [ self.seats-1, 1 ][ self.deal%2 ],
+            PassSchedule::AlwaysLeft          => 1,
+            PassSchedule::NoPassing |
+            PassSchedule::Scatter             => 0,
+        };
+        ( who + offset ) % self.seats
+    }
+    /// To whom the player passes cards in this hand, in the order of the cards passed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut state = GameState::default();
+    /// state.pass_schedule = PassSchedule::Scatter;
+    /// state.apply( Action::Deal{ hands: Cards::deal() }).unwrap();
+    /// assert_eq!( state.pass_receivers( ME ), vec![ LEFT, OPPOSITE, RIGHT ]);
+    ///
+    /// let cards = "2c 3c 4c".parse::<Cards>().unwrap();
+    /// let split = state.split_passing( ME, cards );
+    /// assert_eq!( split[ LEFT ], TWO_OF_CLUBS );
+    /// assert_eq!( split[ RIGHT ], FOUR_OF_CLUBS );
+    /// ```
+    pub fn pass_receivers( &self, who: usize ) -> Vec<usize> {
+        if !self.needs_pass_cards() {
+            Vec::new()
+        } else if self.pass_schedule == PassSchedule::Scatter {
+            self.others().map( |offset| ( who + offset ) % self.seats ).collect()
+        } else {
+            vec![ self.pass_to( who )]
+        }
+    }
+    /// Splits the cards passed by the player into the cards each player receives, indexed by seat.
+    pub fn split_passing( &self, who: usize, cards: Cards ) -> [Cards;MAX_PLAYERS] {
+        let mut split = [NO_CARD;MAX_PLAYERS];
+        let receivers = self.pass_receivers( who );
+        if let [ receiver ] = receivers[..] {
+            split[ receiver ] = cards;
+        } else {
+            receivers.into_iter().zip( cards.iter() ).for_each( |( receiver, card )| split[ receiver ] += card );
+        }
+        split
+    }
+    /// Checks the game rules and returns the broken one, if any.
+    pub fn break_the_rule( &self, who: usize, card: Cards ) -> Option<Rule> {
+        let hand = self.players[who].hand;
+
+        if self.round == 0 { // passing
+            if !hand.contains( card ) {
+                Some( Rule::PassInHand )
+            } else if card.count() != 3 {
+                Some( Rule::PassThreeCards )
+            } else {
+                None
+            }
+        } else { // discarding
+            if !hand.contains( card ) {
+                return Some( Rule::DiscardInHand );
+            }
+            if card.count() != 1 {
+                return Some( Rule::DiscardOneCard );
+            }
+
+            let suit_mask = if self.under_the_gun == who { NO_CARD } else { self.suit_mask };
+            let opening_card = ( self.round == 1 ).then_some( self.opening_card );
+            self.rule_set.break_the_rule( hand, card, suit_mask, opening_card, self.hearts_may_lead() )
+        }
+    }
+    /// The cards in the player's hand which may be passed, any three of them, or `NO_CARD` if the player
+    /// is not to pass.
+    pub fn legal_passes( &self, who: usize ) -> Cards {
+        if self.phase == Phase::Passing && who < self.seats && !self.has_passed( who ) {
+            self.players[who].hand
+        } else {
+            NO_CARD
+        }
+    }
+    /// The cards in the player's hand which may be discarded now, as `GameState::rule_set` tells,
+    /// or `NO_CARD` if it is not the player's turn.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut state = GameState::default();
+    /// let hands = Cards::deal_with( &mut Rng::with_seed( 2024 ));
+    /// state.apply( Action::Deal{ hands }).unwrap();
+    /// assert_eq!( state.legal_passes( ME ), hands[ ME ]);
+    /// assert_eq!( state.legal_plays( ME ), NO_CARD );
+    ///
+    /// for who in PLAYERS {
+    ///     let cards = state.player( who ).hand.pick_three_cards().dest;
+    ///     state.apply( Action::Pass{ who, cards }).unwrap();
+    /// }
+    /// let who = state.next_to_discard();
+    /// assert_eq!( state.legal_passes( who ), NO_CARD );
+    /// assert_eq!( state.legal_plays( who ), TWO_OF_CLUBS );
+    /// ```
+    pub fn legal_plays( &self, who: usize ) -> Cards {
+        if self.phase != Phase::Discarding || who != self.next_to_discard() {
+            return NO_CARD;
+        }
+        self.players[who].hand.iter()
+            .filter( |&card| self.break_the_rule( who, card ).is_none() )
+            .fold( NO_CARD, |legal, card| legal + card )
+    }
+    /// The state as the player observes it: the other players hold as many cards as they really do, but the cards
+    /// unseen by the player in the order of the deck, and the cards they were dealt are hidden, and so are the cards
+    /// they passed, except those the player has received.
+    pub(crate) fn observed_by( &self, who: usize ) -> GameState {
+        let mut observed = self.clone();
+        let mut unseen = ( self.deck() - self.discarded - self.players[ who ].hand ).iter();
+        for i in self.players().filter( |&i| i != who ) {
+            let hand = unseen.by_ref().take( self.players[i].hand.count() as usize ).fold( NO_CARD, |hand, card| hand + card );
+            observed.players[i].hand = hand;
+            observed.passing[i] = hand.iter().take( self.passing[i].count() as usize ).fold( NO_CARD, |cards, card| cards + card );
+        }
+        let mut dealt = self.clone();
+        for record in observed.history.hands.iter_mut() {
+            dealt.deal = record.deal;
+            let received = record.deal != self.deal || self.phase != Phase::Passing;
+            for i in self.players().filter( |&i| i != who ) {
+                record.hands[i] = NO_CARD;
+                record.passes[i] = if received { dealt.split_passing( i, record.passes[i] )[ who ]} else { NO_CARD };
+            }
+        }
+        observed
+    }
+    /// Returns true if any hearts have been discarded in this hand,
+    /// or the Queen of Spades if `RuleSet::queen_breaks` is set.
+    pub fn heart_broken( &self ) -> bool {
+        self.discarded.contains_suit( HEARTS )
+            || ( self.rule_set.queen_breaks && self.discarded.contains( QUEEN_OF_SPADES ))
+    }
+    /// Returns true if hearts may be led, i.e. hearts are broken or `RuleSet::hearts_anytime` is set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut state = GameState::default();
+    /// assert!( !state.hearts_may_lead() );
+    /// state.rule_set.hearts_anytime = true;
+    /// assert!( state.hearts_may_lead() );
+    /// ```
+    pub fn hearts_may_lead( &self ) -> bool {
+        self.rule_set.hearts_anytime || self.heart_broken()
+    }
+    /// Who holds the opening card, the two of clubs by default, or `NOBODY` if it has been discarded.
+    pub fn who_holds_opening_card( &self ) -> usize {
+        for i in self.players() {
+            if self.players[i].hand.contains( self.opening_card ) {
+                return i;
+            }
+        }
+        NOBODY
+    }
+    /// The points of the cards as `GameState::rule_set` tells, e.g. 1 for each heart and 13 for the Queen of Spades
+    /// in the standard rule pack, and -10 for the Jack of Diamonds if it counts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut state = GameState::default();
+    /// assert_eq!( state.points_of( HEARTS + QUEEN_OF_SPADES + JACK_OF_DIAMONDS ), 26 );
+    /// state.rule_set = RuleSet::new( RulePack::Standard, true );
+    /// assert_eq!( state.points_of( HEARTS + QUEEN_OF_SPADES + JACK_OF_DIAMONDS ), 16 );
+    /// ```
+    pub fn points_of( &self, cards: Cards ) -> i32 {
+        self.rule_set.points_of( cards )
+    }
+    /// The suit to follow in current round, represented in u8.
+    pub fn suit( &self ) -> u8 {
+        self.suit_mask.mask_to_suit()
+    }
+    /// Applies the action if it obeys the game rules and returns the resulting events,
+    /// otherwise returns the broken rule and keeps the state unchanged.
+    pub fn apply( &mut self, action: Action ) -> Result<Vec<Event>, Rule> {
+        let mut events = Vec::new();
+        match action {
+            Action::NewGame => {
+                self.reset();
+                events.push( Event::GameStarted );
+            },
+            Action::Deal{ hands } => {
+                if self.phase != Phase::Dealing {
+                    return Err( Rule::OutOfTurn );
+                }
+                self.deal_hands( hands );
+                events.push( Event::Dealt{ deal: self.deal, hands });
+                if self.needs_pass_cards() {
+                    self.phase = Phase::Passing;
+                } else {
+                    self.start_discarding( &mut events );
+                }
+            },
+            Action::Pass{ who, cards } => {
+                if self.phase != Phase::Passing || who >= self.seats || self.has_passed( who ) {
+                    return Err( Rule::OutOfTurn );
+                }
+                if let Some( rule ) = self.break_the_rule( who, cards ) {
+                    return Err( rule );
+                }
+                self.passing[ who ] = cards;
+                events.push( Event::Passed{ who, cards });
+                if self.passing[ ..self.seats ].iter().all( |&cards| cards != NO_CARD ) {
+                    self.exchange( &mut events );
+                    self.start_discarding( &mut events );
+                }
+            },
+            Action::Discard{ who, card } => {
+                if self.phase != Phase::Discarding || who != self.next_to_discard() {
+                    return Err( Rule::OutOfTurn );
+                }
+                if let Some( rule ) = self.break_the_rule( who, card ) {
+                    return Err( rule );
+                }
+                self.discard( who, card );
+                events.push( Event::Discarded{ who, card });
+                if self.next_to_discard() == self.under_the_gun {
+                    self.complete_trick( &mut events );
+                }
+            },
+        }
+        events.iter().for_each( |event| self.history.record( event ));
+        Ok( events )
+    }
+    fn reset( &mut self ) {
+        self.players.iter_mut().for_each( |player| player.reset() );
+        self.winner         = NOBODY;
+        self.deal           = 0_usize;
+        self.phase          = Phase::Dealing;
+        self.passing        = [NO_CARD;MAX_PLAYERS];
+        self.discarded      = NO_CARD;
+        self.suit_mask      = NO_CARD;
+        self.round          = 0_usize;
+        self.under_the_gun  = NOBODY;
+        self.round_winner   = NOBODY;
+        self.high_card      = NO_CARD;
+    }
+    fn deal_hands( &mut self, hands: [Cards;MAX_PLAYERS] ) {
+        self.discarded = NO_CARD;
+        self.suit_mask = NO_CARD;
+        self.deal += 1;
+        self.round = 0;
+        self.passing = [NO_CARD;MAX_PLAYERS];
+        for i in self.players() {
+            self.players[i].hand        = hands[i];
+            self.players[i].discarding  = NO_CARD;
+            self.players[i].taken       = NO_CARD;
+            self.players[i].hand_score  = 0;
+            self.players[i].suit_bits   = BIT_CLUB | BIT_DIAMOND | BIT_SPADE | BIT_HEART;
+        }
+    }
+    fn exchange( &mut self, events: &mut Vec<Event> ) {
+        let split = self.players().map( |j| self.split_passing( j, self.passing[j] )).collect::<Vec<_>>();
+        for i in self.players() {
+            self.players[i].hand = self.players[i].hand - self.passing[i];
+        }
+        for i in self.players() {
+            for j in self.players().filter( |&j| split[j][i] != NO_CARD ) {
+                self.players[i].hand += split[j][i];
+                events.push( Event::Received{ who: i, from: j, cards: split[j][i] });
+            }
+        }
+        self.passing = [NO_CARD;MAX_PLAYERS];
+    }
+    fn start_discarding( &mut self, events: &mut Vec<Event> ) {
+        self.phase = Phase::Discarding;
+        self.next_round();
+        events.push( Event::TrickStarted{ round: self.round, leader: self.under_the_gun });
+    }
+    fn discard( &mut self, who: usize, discarding: Cards ) {
+        self.players[who].hand = self.players[who].hand - discarding;
+        self.players[who].discarding = discarding;
+        match self.suit_mask {
+            CLUBS    if !discarding.contains_suit( CLUBS    ) => self.players[who].suit_bits &= !BIT_CLUB   ,
+            DIAMONDS if !discarding.contains_suit( DIAMONDS ) => self.players[who].suit_bits &= !BIT_DIAMOND,
+            SPADES   if !discarding.contains_suit( SPADES   ) => self.players[who].suit_bits &= !BIT_SPADE  ,
+            HEARTS   if !discarding.contains_suit( HEARTS   ) => self.players[who].suit_bits &= !BIT_HEART  ,
+            _ => (),
+        }
+
+        self.discarded += discarding;
+        if self.suit_mask == NO_CARD {
+            self.suit_mask = discarding.suit_mask();
+        }
+        let card = discarding & self.suit_mask;
+        if self.high_card < card {
+            self.high_card = card;
+            self.round_winner = who;
+        }
+    }
+    fn complete_trick( &mut self, events: &mut Vec<Event> ) {
+        let trick = self.trick();
+        let points = self.keep_score();
+        events.push( Event::TrickWon{ trick, points });
+        if let Some( event ) = self.shoot_the_moon( &trick ) {
+            events.push( event );
+        }
+
+        if self.round == self.rounds() {
+            let hand_scores = self.pool( &self.players.map( |player| player.hand_score ));
+            for i in self.players() {
+                self.players[i].hand_score = hand_scores[i];
+                self.players[i].game_score += hand_scores[i];
+            }
+            events.push( Event::HandScored {
+                hand_scores : self.players.map( |player| player.hand_score ),
+                game_scores : self.players.map( |player| player.game_score ),
+            });
+            let winner = self.get_winner();
+            if winner == NOBODY {
+                self.phase = Phase::Dealing;
+            } else {
+                self.phase = Phase::GameOver;
+                events.push( Event::GameOver{ winner });
+            }
+        } else {
+            self.next_round();
+            events.push( Event::TrickStarted{ round: self.round, leader: self.under_the_gun });
+        }
+    }
+    fn keep_score( &mut self ) -> i32 {
+        let trick = self.players.iter().fold( NO_CARD, |trick, p| trick + p.discarding );
+        let score = self.points_of( trick );
+        let winner = self.round_winner;
+        self.players[ winner ].taken += trick;
+        self.players[ winner ].hand_score += score;
+        score
+    }
+    /// Settles the hand scores if the trick completes a moon, or a sun in the last round if `shoot_the_sun` is set.
+    ///
+    /// If `shoot_the_sun` is set, a moon taken with every trick so far is put off until its shooter loses a trick,
+    /// or announced as a sun in the last round, so that one hand shoots once.
+    ///
+    /// With partnership, the moon scores once per team, to the first seat of the team, before pooling.
+    fn shoot_the_moon( &mut self, trick: &Trick ) -> Option<Event> {
+        let cards = trick.cards.iter().fold( NO_CARD, |cards, &card| cards + card );
+        let penalty_cards = self.rule_set.penalty_cards();
+        let moon_of = |who: usize| self.taken_for_moon( who ) & penalty_cards == penalty_cards;
+        let put_off = self.players().find( |&i| self.shoot_the_sun && moon_of( i ) && self.taken_for_moon( i ) == self.discarded - cards );
+        let who = put_off.unwrap_or( trick.winner );
+
+        let sun = self.shoot_the_sun && self.taken_for_moon( who ) == self.discarded;
+        if sun && trick.round < self.rounds() {
+            return None;
+        }
+        let moon = put_off.is_some() || ( cards.contains_suit( penalty_cards ) && moon_of( who ));
+        if !( sun || moon ) {
+            return None;
+        }
+        let points = self.rule_set.moon_points() * if sun { 2 } else { 1 };
+        let settle = |state: &Self, rule: MoonRule| state.players().map( |i| {
+            let bonus = state.points_of( state.players[i].taken - penalty_cards );
+            if state.team_of( i ) != i {
+                return bonus;
+            }
+            match ( rule, i == state.team_of( who )) {
+                ( MoonRule::SubtractFromSelf, true  ) => bonus - points,
+                ( MoonRule::SubtractFromSelf, false ) |
+                ( _                         , true  ) => bonus,
+                ( _                         , false ) => bonus + points,
+            }
+        }).collect::<Vec<_>>();
+
+        let rule = match self.moon_rule {
+            MoonRule::ShooterChooses => {
+                let hand_scores = self.pool( &settle( self, MoonRule::AddToOthers ));
+                let scores = self.players().map( |i| self.players[i].game_score + hand_scores[i] ).collect::<Vec<_>>();
+                if self.ending.is_over( self.deal, &scores ) && scores.iter().any( |&score| score < scores[ who ]) {
+                    MoonRule::SubtractFromSelf
+                } else {
+                    MoonRule::AddToOthers
+                }
+            },
+            rule => rule,
+        };
+        for ( i, hand_score ) in settle( self, rule ).into_iter().enumerate() {
+            self.players[i].hand_score = hand_score;
+        }
+        Some( Event::ShotTheMoon{ who, sun, rule })
+    }
+    fn get_winner( &mut self ) -> usize {
+        let scores = self.players()
+            .filter( |&i| self.team_of( i ) == i )
+            .map( |i| ( i, self.players[i].game_score, self.players[i].hand_score ))
+            .collect::<Vec<_>>();
+        match self.ending.winner_of( self.deal, &scores ) {
+            Some( winner ) => {
+                self.winner = winner;
+                self.players[ winner ].awards += 1;
+                let partner = self.partner( winner );
+                if let Some( partner ) = self.players.get_mut( partner ) {
+                    partner.awards += 1;
+                }
+                winner
+            },
+            None => NOBODY,
+        }
+    }
+    fn next_round( &mut self ) {
+        self.round += 1;
+        for i in self.players() {
+            self.players[i].discarding = NO_CARD;
+        }
+        if self.round == 1 {
+            self.under_the_gun = self.who_holds_opening_card();
+            self.suit_mask = self.opening_card.suit_mask();
+        } else {
+            self.under_the_gun = self.round_winner;
+            self.suit_mask = NO_CARD;
+        };
+        self.round_winner = NOBODY;
+        self.high_card = NO_CARD;
+    }
+}
+
+impl GameState {
+    /// Writes the state in lines of a key followed by its values, see `GameState::read_line()`.
+    ///
+    /// Cards are written as hexadecimal numbers of their binary representation. The rules are written first, see
+    /// `GameState::write_rules()`, followed by the other fields of the state. Each player is written as "player",
+    /// the seat, hand, discarding, passing, taken cards, hand score, game score, suit bits and times of winning.
+    /// The history follows, see `History::write_lines()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use heartless::*;
+    ///
+    /// let mut state = GameState::default();
+    /// state.apply( Action::Deal{ hands: Cards::deal() }).unwrap();
+    ///
+    /// let mut lines = String::new();
+    /// state.write_lines( &mut lines );
+    ///
+    /// let mut loaded = GameState::default();
+    /// lines.lines().for_each( |line| loaded.read_line( line ).unwrap() );
+    /// assert_eq!( loaded.phase(), Phase::Passing );
+    /// assert_eq!( loaded.player( ME ).hand, state.player( ME ).hand );
+    /// ```
+    pub fn write_lines( &self, lines: &mut String ) {
+        let phase = match self.phase {
+            Phase::Dealing    => "dealing",
+            Phase::Passing    => "passing",
+            Phase::Discarding => "discarding",
+            Phase::GameOver   => "game_over",
+        };
+        self.write_rules( lines );
+        let mut line = |line: String| { lines.push_str( &line ); lines.push( '\n' ); };
+        line( format!( "deal {}", self.deal ));
+        line( format!( "phase {phase}" ));
+        line( format!( "winner {}", self.winner ));
+        line( format!( "discarded {:#x}", self.discarded.0 ));
+        line( format!( "suit_mask {:#x}", self.suit_mask.0 ));
+        line( format!( "round {}", self.round ));
+        line( format!( "under_the_gun {}", self.under_the_gun ));
+        line( format!( "round_winner {}", self.round_winner ));
+        line( format!( "high_card {:#x}", self.high_card.0 ));
+        for who in self.players() {
+            let p = &self.players[ who ];
+            line( format!( "player {who} {:#x} {:#x} {:#x} {:#x} {} {} {} {}",
+                p.hand.0, p.discarding.0, self.passing[ who ].0, p.taken.0, p.hand_score, p.game_score, p.suit_bits, p.awards ));
+        }
+        self.history.write_lines( lines );
+    }
+    /// Writes the rules of the game in lines: "seats", "opening_card", "rule_set", "moon_rule", "shoot_the_sun",
+    /// "pass_schedule", "partnership" and "ending".
+    ///
+    /// The rule set is written as the rule pack followed by its switches in the order of declaration. The ending is
+    /// written as the target score, the number of hands or "-" if not given, and the tie break.
+    pub fn write_rules( &self, lines: &mut String ) {
+        let mut line = |line: String| { lines.push_str( &line ); lines.push( '\n' ); };
+        line( format!( "seats {}", self.seats ));
+        line( format!( "opening_card {:#x}", self.opening_card.0 ));
+        line( format!( "rule_set {} {} {} {} {} {}",
+            clap::ValueEnum::to_possible_value( &self.rule_set.pack ).map( |value| value.get_name().to_owned() ).unwrap_or_default(),
+            self.rule_set.jack_of_diamonds, self.rule_set.points_on_first, self.rule_set.queen_breaks,
+            self.rule_set.hearts_anytime, self.rule_set.any_opening_suit ));
+        line( format!( "moon_rule {}", clap::ValueEnum::to_possible_value( &self.moon_rule ).map( |value| value.get_name().to_owned() ).unwrap_or_default() ));
+        line( format!( "shoot_the_sun {}", self.shoot_the_sun ));
+        line( format!( "pass_schedule {}", clap::ValueEnum::to_possible_value( &self.pass_schedule ).map( |value| value.get_name().to_owned() ).unwrap_or_default() ));
+        line( format!( "partnership {}", clap::ValueEnum::to_possible_value( &self.partnership ).map( |value| value.get_name().to_owned() ).unwrap_or_default() ));
+        line( format!( "ending {} {} {}", self.ending.target_score, self.ending.hands.map_or( "-".to_owned(), |hands| hands.to_string() ),
+            clap::ValueEnum::to_possible_value( &self.ending.tie_break ).map( |value| value.get_name().to_owned() ).unwrap_or_default() ));
+    }
+    /// Restores a line written by `GameState::write_rules()`, or returns `None` if the line is malformed or tells
+    /// no rule.
+    pub fn read_rule( &mut self, line: &str ) -> Option<()> {
+        const KEYS: [&str; 8] = [ "seats", "opening_card", "rule_set", "moon_rule", "shoot_the_sun", "pass_schedule", "partnership", "ending" ];
+        KEYS.contains( &line.split_whitespace().next()? ).then( || self.read_line( line ))?
+    }
+    /// Restores a line written by `GameState::write_lines()`, or returns `None` if the line is malformed.
+    pub fn read_line( &mut self, line: &str ) -> Option<()> {
+        fn cards( word: Option<&str> ) -> Option<Cards> {
+            u64::from_str_radix( word?.strip_prefix( "0x" )?, 16 ).ok().map( Cards )
+        }
+        fn number<T: std::str::FromStr>( word: Option<&str> ) -> Option<T> {
+            word?.parse().ok()
+        }
+
+        let mut words = line.split_whitespace();
+        match words.next()? {
+            "seats"         => self.seats         = number( words.next() ).filter( |seats| (3..=MAX_PLAYERS).contains( seats ))?,
+            "opening_card"  => self.opening_card  = cards( words.next() )?,
+            "deal"          => self.deal          = number( words.next() )?,
+            "phase"         => self.phase         = match words.next()? {
+                "dealing"    => Phase::Dealing,
+                "passing"    => Phase::Passing,
+                "discarding" => Phase::Discarding,
+                "game_over"  => Phase::GameOver,
+                _            => return None,
+            },
+            "winner"        => self.winner        = number( words.next() )?,
+            "discarded"     => self.discarded     = cards( words.next() )?,
+            "suit_mask"     => self.suit_mask     = cards( words.next() )?,
+            "round"         => self.round         = number( words.next() )?,
+            "under_the_gun" => self.under_the_gun = number( words.next() )?,
+            "round_winner"  => self.round_winner  = number( words.next() )?,
+            "high_card"     => self.high_card     = cards( words.next() )?,
+            "rule_set"      => {
+                self.rule_set = RuleSet::new( clap::ValueEnum::from_str( words.next()?, false ).ok()?, number( words.next() )? );
+                self.rule_set.points_on_first  = number( words.next() )?;
+                self.rule_set.queen_breaks     = number( words.next() )?;
+                self.rule_set.hearts_anytime   = number( words.next() )?;
+                self.rule_set.any_opening_suit = number( words.next() )?;
+            },
+            "moon_rule"     => self.moon_rule     = clap::ValueEnum::from_str( words.next()?, false ).ok()?,
+            "shoot_the_sun" => self.shoot_the_sun = number( words.next() )?,
+            "pass_schedule" => self.pass_schedule = clap::ValueEnum::from_str( words.next()?, false ).ok()?,
+            "partnership"   => self.partnership   = clap::ValueEnum::from_str( words.next()?, false ).ok()?,
+            "ending"        => {
+                self.ending.target_score = number( words.next() )?;
+                self.ending.hands = match words.next()? {
+                    "-"   => None,
+                    hands => Some( number( Some( hands )).filter( |&hands| hands != 0 )? ),
+                };
+                self.ending.tie_break = clap::ValueEnum::from_str( words.next()?, false ).ok()?;
+            },
+            "player"        => {
+                let who: usize = number( words.next() )?;
+                let p = self.players.get_mut( who )?;
+                p.hand       = cards( words.next() )?;
+                p.discarding = cards( words.next() )?;
+                self.passing[ who ] = cards( words.next() )?;
+                p.taken      = cards( words.next() )?;
+                p.hand_score = number( words.next() )?;
+                p.game_score = number( words.next() )?;
+                p.suit_bits  = number( words.next() )?;
+                p.awards     = number( words.next() )?;
+            },
+            _ => return self.history.read_line( line ),
+        }
+        words.next().is_none().then_some(())
+    }
+}
+
+#[cfg( test )]
+mod tests {
+    use super::*;
+
+    /// Plays the lowest legal card of each player until the hand is scored.
+    fn play_out( state: &mut GameState ) -> Vec<Event> {
+        let mut events = Vec::new();
+        while state.phase() == Phase::Discarding {
+            let who = state.next_to_discard();
+            let card = state.legal_plays( who ).iter().next().unwrap();
+            events.extend( state.apply( Action::Discard{ who, card }).unwrap() );
+        }
+        events
+    }
+
+    /// The hands of a moon shot by `ME` and `OPPOSITE` together, without passing: `ME` takes 7 hearts
+    /// and `OPPOSITE` takes 6 hearts and the Queen of Spades.
+    fn joint_moon( partnership: Partnership ) -> GameState {
+        let cards = |text: &str| text.parse::<Cards>().unwrap();
+        let mut state = GameState{ partnership, ..GameState::default() };
+        state.read_line( "deal 3" ).unwrap();
+        state.apply( Action::Deal{ hands: [
+            cards( "8c 9c 0c Jc Qc Kc Ac 2d 3d 4d 5d 6d 7d" ),
+            SPADES,
+            cards( "2c 3c 4c 5c 6c 7c 8d 9d 0d Jd Qd Kd Ad" ),
+            HEARTS,
+            NO_CARD,
+            NO_CARD,
+        ]}).unwrap();
+        state
+    }
+
+    #[test]
+    fn deals_three_players() {
+        let mut state = GameState{ seats: 3, ..GameState::default() };
+        let hands = Cards::deal_among( 3, &mut Rng::with_seed( 2024 ));
+        state.apply( Action::Deal{ hands }).unwrap();
+        assert_eq!( state.rounds(), 17 );
+        assert!( state.players().all( |who| state.player( who ).hand.count() == 17 ));
+        assert_eq!( state.pass_receivers( 2 ), vec![ ME ]);
+
+        for who in state.players() {
+            let cards = state.player( who ).hand.pick_three_cards().dest;
+            state.apply( Action::Pass{ who, cards }).unwrap();
+        }
+        assert_eq!( state.legal_plays( state.next_to_discard() ), TWO_OF_CLUBS );
+        play_out( &mut state );
+        assert_eq!( state.phase(), Phase::Dealing );
+        assert_eq!( state.players().map( |who| state.player( who ).hand_score ).sum::<i32>(), 26 );
+    }
+
+    #[test]
+    fn follows_pass_schedules() {
+        let mut state = GameState::default();
+        let receivers = |state: &mut GameState, schedule: PassSchedule, deal: usize| {
+            state.pass_schedule = schedule;
+            state.read_line( &format!( "deal {deal}" )).unwrap();
+            state.pass_receivers( ME )
+        };
+        assert_eq!( receivers( &mut state, PassSchedule::LeftRightAcrossHold, 1 ), vec![ LEFT ]);
+        assert_eq!( receivers( &mut state, PassSchedule::LeftRightAcrossHold, 2 ), vec![ RIGHT ]);
+        assert_eq!( receivers( &mut state, PassSchedule::LeftRightAcrossHold, 3 ), vec![ OPPOSITE ]);
+        assert_eq!( receivers( &mut state, PassSchedule::LeftRightAcrossHold, 4 ), vec![]);
+        assert_eq!( receivers( &mut state, PassSchedule::LeftRight, 2 ), vec![ RIGHT ]);
+        assert_eq!( receivers( &mut state, PassSchedule::AlwaysLeft, 4 ), vec![ LEFT ]);
+        assert_eq!( receivers( &mut state, PassSchedule::NoPassing, 1 ), vec![]);
+        assert_eq!( receivers( &mut state, PassSchedule::Scatter, 4 ), vec![ LEFT, OPPOSITE, RIGHT ]);
+    }
+
+    #[test]
+    fn partners_shoot_the_moon_jointly() {
+        let mut state = joint_moon( Partnership::JointMoon );
+        let events = play_out( &mut state );
+        assert!( events.contains( &Event::ShotTheMoon{ who: OPPOSITE, sun: false, rule: MoonRule::AddToOthers }));
+        assert_eq!( state.players().map( |who| state.player( who ).game_score ).collect::<Vec<_>>(), vec![ 0, 26, 0, 26 ]);
+
+        let mut state = joint_moon( Partnership::SoloMoon );
+        let events = play_out( &mut state );
+        assert!( !events.iter().any( |event| matches!( event, Event::ShotTheMoon{ .. })));
+        assert_eq!( state.players().map( |who| state.player( who ).game_score ).collect::<Vec<_>>(), vec![ 26, 0, 26, 0 ]);
+    }
+
+    #[test]
+    fn shoots_the_sun_once() {
+        let cards = |text: &str| text.parse::<Cards>().unwrap();
+        let shots = |opening_card: Cards, hands: [Cards;MAX_PLAYERS]| {
+            let mut state = GameState{ opening_card, shoot_the_sun: true, ..GameState::default() };
+            state.read_line( "deal 3" ).unwrap();
+            state.apply( Action::Deal{ hands }).unwrap();
+            let mut events = Vec::new();
+            while state.phase() == Phase::Discarding { // `ME` plays low, and the others dump the penalty cards
+                let who = state.next_to_discard();
+                let mut legal = state.legal_plays( who ).iter();
+                let card = if who == ME { legal.next() } else { legal.next_back() }.unwrap();
+                events.extend( state.apply( Action::Discard{ who, card }).unwrap() );
+            }
+            let shots = events.into_iter().filter( |event| matches!( event, Event::ShotTheMoon{ .. })).collect::<Vec<_>>();
+            ( shots, state.players().map( |who| state.player( who ).hand_score ).collect::<Vec<_>>() )
+        };
+
+        let ( events, hand_scores ) = shots( TWO_OF_CLUBS, [
+            CLUBS,
+            cards( "2h 3h 4h 5h 6h 2s 3s 4s 5s 6s 7s 8s 9s" ),
+            cards( "7h 8h 9h 0h 2d 3d 4d 5d 6d 7d 8d 9d 0d" ),
+            cards( "Jh Qh Kh Ah Jd Qd Kd Ad 0s Js Qs Ks As" ),
+            NO_CARD,
+            NO_CARD,
+        ]);
+        assert_eq!( events, vec![ Event::ShotTheMoon{ who: ME, sun: true, rule: MoonRule::AddToOthers }]);
+        assert_eq!( hand_scores, vec![ 0, 52, 52, 52 ]);
+
+        let ( events, hand_scores ) = shots( ACE_OF_CLUBS, [
+            cards( "5c 6c 7c 8c 9c 0c Jc Qc Kc Ac 2d 3d 4d" ),
+            cards( "2c 3c 4c 2h 3h 4h 5h 6h 2s 3s 4s 5s 6s" ),
+            cards( "7h 8h 9h 0h 5d 6d 7d 8d 9d 0d Jd Qd Kd" ),
+            cards( "Jh Qh Kh Ah Ad 7s 8s 9s 0s Js Qs Ks As" ),
+            NO_CARD,
+            NO_CARD,
+        ]);
+        assert_eq!( events, vec![ Event::ShotTheMoon{ who: ME, sun: false, rule: MoonRule::AddToOthers }]);
+        assert_eq!( hand_scores, vec![ 0, 26, 26, 26 ]);
+    }
+
+    #[test]
+    fn allows_penalty_cards_held_alone() {
+        let cards = |text: &str| text.parse::<Cards>().unwrap();
+        for ( pack, left, right ) in [
+            ( RulePack::Standard  , "Qs 3h 4h 5h 6h 7h 8h 9h 0h Jh Qh Kh Ah", "2s 3s 4s 5s 6s 7s 8s 9s 0s Js Ks As 2h" ),
+            ( RulePack::BlackMaria, "Qs Ks As 5h 6h 7h 8h 9h 0h Jh Qh Kh Ah", "2s 3s 4s 5s 6s 7s 8s 9s 0s Js 2h 3h 4h" ),
+        ] {
+            let mut state = GameState{ rule_set: RuleSet::new( pack, false ), ..GameState::default() };
+            state.read_line( "deal 3" ).unwrap();
+            state.apply( Action::Deal{ hands: [ CLUBS, cards( left ), DIAMONDS, cards( right ), NO_CARD, NO_CARD ]}).unwrap();
+            state.apply( Action::Discard{ who: ME, card: TWO_OF_CLUBS }).unwrap();
+            assert_eq!( state.legal_plays( LEFT ), cards( left ));
+            let position = crate::solver::Position::new( &state, std::array::from_fn( |who| state.player( who ).hand ));
+            assert_eq!( position.legal( &state ), state.legal_plays( LEFT ));
+        }
+    }
+
+    #[test]
+    fn opens_with_a_penalty_suit() {
+        let cards = |text: &str| text.parse::<Cards>().unwrap();
+        let mut state = GameState{ opening_card: TWO_OF_SPADES, ..GameState::default() };
+        state.read_line( "deal 3" ).unwrap();
+        state.apply( Action::Deal{ hands: [
+            TWO_OF_SPADES + CLUBS - ACE_OF_CLUBS,
+            QUEEN_OF_SPADES + HEARTS - ACE_OF_HEARTS,
+            cards( "3s 4s 5s 6s 7s 8s 9s 0s Js Ks As Ac Ah" ),
+            DIAMONDS,
+            NO_CARD,
+            NO_CARD,
+        ]}).unwrap();
+        assert_eq!( state.legal_plays( ME ), TWO_OF_SPADES );
+        state.apply( Action::Discard{ who: ME, card: TWO_OF_SPADES }).unwrap();
+        assert_eq!( state.legal_plays( LEFT ), QUEEN_OF_SPADES );
+        state.apply( Action::Discard{ who: LEFT, card: QUEEN_OF_SPADES }).unwrap();
+        assert_eq!( state.legal_plays( OPPOSITE ), SPADES - TWO_OF_SPADES - QUEEN_OF_SPADES );
+        play_out( &mut state );
+        assert_eq!( state.phase(), Phase::Dealing );
+        assert_eq!( state.players().map( |who| state.player( who ).hand_score ).sum::<i32>(), 26 );
+
+        let mut state = GameState{ opening_card: TWO_OF_HEARTS, ..GameState::default() };
+        state.read_line( "deal 3" ).unwrap();
+        state.apply( Action::Deal{ hands: [ HEARTS, CLUBS, DIAMONDS, SPADES, NO_CARD, NO_CARD ]}).unwrap();
+        assert_eq!( state.legal_plays( ME ), TWO_OF_HEARTS );
+        state.apply( Action::Discard{ who: ME, card: TWO_OF_HEARTS }).unwrap();
+        assert_eq!( state.legal_plays( LEFT ), CLUBS );
+        state.apply( Action::Discard{ who: LEFT, card: TWO_OF_CLUBS }).unwrap();
+        state.apply( Action::Discard{ who: OPPOSITE, card: TWO_OF_DIAMONDS }).unwrap();
+        assert_eq!( state.legal_plays( RIGHT ), SPADES - QUEEN_OF_SPADES );
+    }
+
+    #[test]
+    fn hides_other_hands_from_the_observer() {
+        let mut state = GameState::default();
+        state.apply( Action::Deal{ hands: Cards::deal_with( &mut Rng::with_seed( 2024 ))}).unwrap();
+        state.apply( Action::Pass{ who: LEFT, cards: state.player( LEFT ).hand.pick_three_cards().dest }).unwrap();
+        let observed = state.observed_by( ME );
+        assert_eq!( observed.player( ME ).hand, state.player( ME ).hand );
+        assert!( observed.has_passed( LEFT ));
+        for who in [ LEFT, OPPOSITE, RIGHT ] {
+            assert_eq!( observed.player( who ).hand.count(), 13 );
+            assert_eq!( observed.player( who ).hand & state.player( ME ).hand, NO_CARD );
+            assert_eq!( observed.history().hands[0].hands[ who ], NO_CARD );
+            assert_eq!( observed.history().hands[0].passes[ who ], NO_CARD );
+        }
+        assert_eq!( state.observed_by( OPPOSITE ).history().hands[0].passes[ LEFT ], NO_CARD );
+
+        for who in [ ME, OPPOSITE, RIGHT ] {
+            state.apply( Action::Pass{ who, cards: state.player( who ).hand.pick_three_cards().dest }).unwrap();
+        }
+        let passes = state.history().hands[0].passes;
+        let observed = state.observed_by( OPPOSITE );
+        assert_eq!( observed.history().hands[0].passes[ LEFT ], passes[ LEFT ]);
+        assert_eq!( observed.history().hands[0].passes[ RIGHT ], NO_CARD );
+    }
+
+    #[test]
+    fn ends_after_the_hands() {
+        let mut state = GameState::with_ending( Ending{ target_score: 100, hands: Some( 1 ), tie_break: TieBreak::FirstSeat });
+        state.pass_schedule = PassSchedule::NoPassing;
+        state.apply( Action::Deal{ hands: Cards::deal_with( &mut Rng::with_seed( 2024 ))}).unwrap();
+        let events = play_out( &mut state );
+        assert!( matches!( events.last(), Some( Event::GameOver{ .. })));
+        assert_eq!( state.phase(), Phase::GameOver );
+    }
+
+    #[test]
+    fn saves_the_ending() {
+        for hands in [ None, Some( 1 )] {
+            let state = GameState::with_ending( Ending{ target_score: 50, hands, tie_break: TieBreak::LastHand });
+            let mut lines = String::new();
+            state.write_lines( &mut lines );
+            let mut loaded = GameState::default();
+            lines.lines().for_each( |line| loaded.read_line( line ).unwrap() );
+            assert_eq!( loaded.ending, state.ending );
+        }
+        assert_eq!( GameState::default().read_line( "ending 100 0 play_on" ), None );
+    }
+}
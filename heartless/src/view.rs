@@ -0,0 +1,129 @@
+//! The view of one player, the information it may legitimately observe, handed to every strategy.
+
+use crate::*;
+
+/// What a player may observe of the game: its own hand, the trick in progress, the cards discarded so far,
+/// the suits others have shown void in, the cards it passed and received, and the scores.
+///
+/// Other players' hands are never exposed.
+///
+/// # Example
+///
+/// ```rust
+/// use heartless::*;
+///
+/// let mut game = Game::with_config( cli::Config::from_args([ "heartless", "--seed", "2024" ]));
+/// game.deal_cards().unwrap();
+/// let view = game.view_of( LEFT );
+/// assert_eq!( view.who(), LEFT );
+/// assert_eq!( view.hand(), game.player( LEFT ).hand );
+/// assert_eq!( view.round(), 0 );
+/// assert_eq!( view.discarded(), NO_CARD );
+/// assert_eq!( view.suit_chars( RIGHT ), "hsdc" );
+/// ```
+#[derive( Clone, Copy )]
+pub struct PlayerView<'a> {
+    game : &'a Game,
+    who  : usize,
+}
+
+impl<'a> PlayerView<'a> {
+    /// Builds the view of the player, see `Game::view_of()`.
+    pub(crate) fn new( game: &'a Game, who: usize ) -> Self {
+        PlayerView{ game, who }
+    }
+    /// The game state as observed by this player, for the built-in strategies, see `GameState::observed_by()`.
+    pub(crate) fn observed( &self ) -> GameState {
+        self.game.observed_by( self.who )
+    }
+    /// The player viewing the game.
+    pub fn who( &self ) -> usize {
+        self.who
+    }
+    /// The number of players in this game.
+    pub fn seats( &self ) -> usize {
+        self.game.seats
+    }
+    /// The cards in the player's own hand.
+    pub fn hand( &self ) -> Cards {
+        self.game.player( self.who ).hand
+    }
+    /// The current round, 1~, or 0 in pass phase.
+    pub fn round( &self ) -> usize {
+        self.game.round
+    }
+    /// The trick in progress, with `NO_CARD` for the players not discarded yet.
+    pub fn trick( &self ) -> Trick {
+        self.game.trick()
+    }
+    /// The suit to follow in the trick in progress, or `NO_SUIT` before the lead.
+    pub fn suit( &self ) -> u8 {
+        self.game.suit()
+    }
+    /// The highest card of the suit to follow in the trick in progress, see `Trick::winner`.
+    pub fn high_card( &self ) -> Cards {
+        self.game.high_card
+    }
+    /// The cards discarded so far in this hand, the trick in progress included.
+    pub fn discarded( &self ) -> Cards {
+        self.game.discarded
+    }
+    /// Returns false if the player has shown void in the given suit, otherwise returns true.
+    pub fn may_have( &self, who: usize, suit: u8 ) -> bool {
+        self.game.player_may_have( who, suit )
+    }
+    /// The suits the player may hold, see `Player::suit_chars()`.
+    pub fn suit_chars( &self, who: usize ) -> &'static str {
+        self.game.player( who ).suit_chars()
+    }
+    /// To whom this player passes cards in this hand, none if no cards are passed, see `GameState::pass_receivers()`.
+    pub fn pass_receivers( &self ) -> Vec<usize> {
+        self.game.pass_receivers( self.who )
+    }
+    /// The cards this player passed in this hand.
+    pub fn passed( &self ) -> Cards {
+        self.game.history().hands.last().map( |record| record.passes[ self.who ]).unwrap_or_default()
+    }
+    /// The cards this player received in this hand, known after all the players have passed.
+    pub fn received( &self ) -> Cards {
+        match self.game.history().hands.last() {
+            Some( record ) if self.game.phase() != Phase::Passing => self.game.players().fold( NO_CARD, |cards, from| {
+                cards + self.game.split_passing( from, record.passes[ from ])[ self.who ]
+            }),
+            _ => NO_CARD,
+        }
+    }
+    /// The completed tricks of this hand, with the points taken by their winners.
+    pub fn tricks( &self ) -> &'a [(Trick,i32)] {
+        self.game.history().hands.last().map( |record| &record.tricks[..] ).unwrap_or_default()
+    }
+    /// The points the player took in this hand.
+    pub fn hand_score( &self, who: usize ) -> i32 {
+        self.game.player( who ).hand_score
+    }
+    /// The points the player scored in this game.
+    pub fn game_score( &self, who: usize ) -> i32 {
+        self.game.player( who ).game_score
+    }
+    /// The partner sitting opposite to the player, or `NOBODY` if players play alone.
+    pub fn partner( &self, who: usize ) -> usize {
+        self.game.partner( who )
+    }
+    /// The rules in force.
+    pub fn rule_set( &self ) -> &'a RuleSet {
+        &self.game.rule_set
+    }
+    /// The points of the cards in the rules in force.
+    pub fn points_of( &self, cards: Cards ) -> i32 {
+        self.game.points_of( cards )
+    }
+    /// The cards this player may pass now, or discard now, see `GameState::legal_passes()` and
+    /// `GameState::legal_plays()`.
+    pub fn legal( &self ) -> Cards {
+        if self.game.round == 0 { self.game.legal_passes( self.who )} else { self.game.legal_plays( self.who )}
+    }
+    /// Returns the rule which this player's passing/discarding the cards would break, if any.
+    pub fn break_the_rule( &self, cards: Cards ) -> Option<Rule> {
+        self.game.break_the_rule( self.who, cards )
+    }
+}
@@ -11,7 +11,6 @@ use std::{
     collections::{HashSet, VecDeque},
     fmt::{self, Display},
     rc::Rc,
-    thread,
 };
 
 use tcl::*;
@@ -23,19 +22,17 @@ mod and_extra;
 use and_extra::*;
 
 const FOR_TK: u32 = ASCII_SUIT | LOWERCASE_RANK | NO_SHARED_SUIT;
-const SCORING_CARDS: [&'static str; 14] = ["qs","2h","3h","4h","5h","6h","7h","8h","9h","0h","jh","qh","kh","ah"];
+const SCORING_CARDS: [&str; 14] = ["qs","2h","3h","4h","5h","6h","7h","8h","9h","0h","jh","qh","kh","ah"];
 const DISCARD_ORDER: [usize; 13] = [0,12,1,11,2,10,3,9,4,8,5,7,6];
+const DISCARD_X: [f64; 4] = [0.48, 0.34, 0.48, 0.62];
+const DISCARD_Y: [f64; 4] = [0.60, 0.41, 0.21, 0.41];
 
 #[derive( Clone, Copy, PartialEq, Eq )]
 enum State {
     Welcome,
     Dealing,
-    Dealed,
-    Passing,
-    Received,
     ToAccept,
     Discarding,
-    Obey,
     Discarded,
     CheckWinner,
     ShowWinner,
@@ -101,17 +98,13 @@ impl Animation {
 struct Animations( Rc<RefCell<AnimationsInner>> );
 
 struct AnimationsInner {
-    queue         : VecDeque<Animation>,
-    the_suits     : Rc<Cell<Cards>>,
-    under_the_gun : Rc<Cell<usize>>,
+    queue : VecDeque<Animation>,
 }
 
 impl Animations {
-    fn new( the_suits: Rc<Cell<Cards>>, under_the_gun: Rc<Cell<usize>> ) -> Self {
+    fn new() -> Self {
         Animations( Rc::new( RefCell::new( AnimationsInner {
             queue: VecDeque::new(),
-            the_suits,
-            under_the_gun,
         })))
     }
     fn discard( &self, who: usize, card: &str, to_discard: TkButton<()>, tk: Tk<()> ) -> InterpResult<()> {
@@ -120,13 +113,10 @@ impl Animations {
             None => return Ok(()),
         };
 
-        let x_of = [0.48, 0.34, 0.48, 0.62];
-        let y_of = [0.60, 0.41, 0.21, 0.41];
-
         self.0.borrow_mut().queue.push_back( Animation::Move( Move {
             card       : card.to_owned(),
             src        ,
-            dest       : RelCoord{ x: x_of[who], y: y_of[who] },
+            dest       : RelCoord{ x: DISCARD_X[who], y: DISCARD_Y[who] },
             percent    : 0,
             done_state : (who == ME).then_some( State::Discarded ),
             parallel   : false,
@@ -135,13 +125,6 @@ impl Animations {
         if who == ME {
             Ok(())
         } else {
-            let gun = self.0.borrow().under_the_gun.get();
-            if gun != NOBODY {
-                if who == (gun+3) % 4 {
-                    self.0.borrow().the_suits.set( NO_CARD );
-                }
-            }
-
             to_discard.place_forget()
         }
     }
@@ -153,46 +136,7 @@ impl Animations {
         }));
     }
     fn is_empty( &self ) -> bool { self.0.borrow().queue.is_empty() }
-}
-
-fn set_card_line<'card, 'line:'card>(
-        who : usize,
-        line: &'line str,
-        card: &mut &'card str,
-        gun: &mut usize,
-        win: &mut usize
-) {
-    *card = line.trim_start_matches(':').trim_end_matches('.');
-    if line.starts_with(':') { *gun = who; }
-    if line.ends_with('.') { *win = who; }
-}
-
-fn set_discarding_cards<'card, 'line, Lines>(
-    lines : &mut Lines,
-    cards : &mut [&'card str; 4],
-      gun : &mut usize,
-      win : &mut usize
-)
-where 'line : 'card
-    , Lines : Iterator<Item=&'line str>
-{
-    let opposite_line = lines.next().unwrap().trim();
-    set_card_line( OPPOSITE, opposite_line, &mut cards[OPPOSITE], gun, win );
-
-    let left_right_line = lines.next().unwrap();
-    let num_of_starting_spaces = left_right_line.chars().take_while( |&c| c == ' ' ).fold( 0, |n,_| n+1 );
-    let left_right_line = left_right_line.trim();
-    let mut left_and_right = left_right_line.split_whitespace();
-    if num_of_starting_spaces <= 4 {
-        let left_line = left_and_right.next().unwrap();
-        set_card_line( LEFT, left_line, &mut cards[LEFT], gun, win );
-    }
-    if let Some( right_line ) = left_and_right.next() {
-        set_card_line( RIGHT, right_line, &mut cards[RIGHT], gun, win );
-    }
-
-    let my_line = lines.next().unwrap().trim();
-    set_card_line( ME, my_line, &mut cards[ME], gun, win );
+    fn clear( &self ) { self.0.borrow_mut().queue.clear(); }
 }
 
 #[derive( Clone )]
@@ -226,8 +170,9 @@ impl KbInput {
             if s.len() == 1 {
                 false
             } else {
-                let ch = s.chars().last();
-                ch.map( |ch| *s = ch.into() );
+                if let Some( ch ) = s.chars().last() {
+                    *s = ch.into();
+                }
                 true
             }
         }
@@ -253,6 +198,49 @@ impl Display for KbInput {
 
 type Deck = Rc<IndexMap<&'static str,TkButton<()>>>;
 
+/// Places the card backs of the player holding `cards_left` cards, which have been discarded in `DISCARD_ORDER`.
+fn place_card_backs( card_back_btns: &[TkButton<()>], who: usize, cards_left: usize ) -> InterpResult<()> {
+    DISCARD_ORDER[ 13-cards_left.. ].iter().try_for_each( |&i| {
+        let btn = card_back_btns[ 13*(who-1) + i ];
+        let i = i as f64;
+        match who {
+            LEFT     => btn.place( -relx(0.14) -rely(0.20+0.03*i) )?,
+            OPPOSITE => btn.place( -relx(0.34+0.02*i) -rely(0.01) )?,
+            _        => btn.place( -relx(0.79) -rely(0.20+0.03*i) )?,
+        };
+        Ok(())
+    })
+}
+
+fn place_score_lbs( score_lbs: &[TkLabel<()>] ) -> InterpResult<()> {
+    score_lbs[ ME       ].place( -relx(0.51) -rely(0.51) )?;
+    score_lbs[ LEFT     ].place( -relx(0.47) -rely(0.46) )?;
+    score_lbs[ OPPOSITE ].place( -relx(0.51) -rely(0.41) )?;
+    score_lbs[ RIGHT    ].place( -relx(0.55) -rely(0.46) )?;
+    Ok(())
+}
+
+fn update_score_lbs( score_lbs: &[TkLabel<()>], game: &Game ) -> InterpResult<()> {
+    let moon_shooter = game.moon_shooter();
+    PLAYERS.zip( score_lbs.iter() ).try_for_each( |(who, score_lb)| {
+        let player = game.player( who );
+        let shot = match moon_shooter {
+            Some(( shooter, sun )) if shooter == who => if sun { " sun" } else { " moon" },
+            _ => "",
+        };
+        score_lb.configure( -text(&*format!( "{}/{}{shot}", player.hand_score, player.game_score )) )
+    })
+}
+
+fn place_pass_btn( pass_btn: TkButton<()>, game: &Game ) -> InterpResult<()> {
+    let receiver = match game.pass_receivers( ME )[..] {
+        [ receiver ] => ["nobody", "left", "opposite", "right"][ receiver ],
+        _            => "left, opposite and right",
+    };
+    pass_btn.place( -relx(0.44) -rely(0.65) )?
+        .configure( -state("disabled") -text(&*format!( "Pass 3 cards to {receiver}" )) )
+}
+
 fn restart_game( deck: &Deck, start_btn: TkButton<()> ) -> InterpResult<()> {
     deck.values().try_for_each( |btn| btn.place_forget() )?;
     start_btn.invoke()?;
@@ -349,7 +337,7 @@ impl MyDiscard {
     }
     fn select( &self, card: &str ) -> InterpResult<()> {
         self.unselect()?;
-        select_card( &self.deck, &card )?;
+        select_card( &self.deck, card )?;
         self.card.set( heartless::LNAMES.iter().find( |&&name| name == card ).unwrap() );
         Ok(())
     }
@@ -383,6 +371,10 @@ fn hearts_in_relative_coordinates() -> Vec<(f64,f64)> {
 
 fn main() -> TkResult<()> {
     let mut config = heartless::cli::Config::from_args( std::env::args_os() );
+    if config.players != 4 {
+        eprintln!( "The GUI frontend only supports 4 players." );
+        std::process::exit( 2 );
+    }
 
     // for split_whitespace() and tk widgets whose paths are all ascii chars in lowercase.
     config.ascii_suit     = true;
@@ -395,9 +387,12 @@ fn main() -> TkResult<()> {
     let automatic = config.automatic;
     config.automatic = false;
 
-    let mut game = Game::with_config( config );
-    let game_io = game.altio.clone();
-    thread::spawn( move || game.main_loop() );
+    let no_undo = config.no_undo;
+
+    let games_left = Rc::new( Cell::new( config.count ));
+
+    let game = Rc::new( RefCell::new( Game::with_config( config )));
+    let game_io = game.borrow().altio.clone();
 
     let tk = Tk::new(())?;
     let root = tk.root();
@@ -431,27 +426,29 @@ fn main() -> TkResult<()> {
     let the_state     = Rc::new( Cell::new( State::Welcome ));
     let quit_msg      = Rc::new( RefCell::new( None ));
 
-    let under_the_gun = Rc::new( Cell::new( NOBODY )); // for the_suits
-    let round_cnt     = Rc::new( Cell::new( 0_usize ));
-    let round_winner  = Rc::new( Cell::new( 0_usize )); // for the_suits
-
-    let my_recvs    = Rc::new( RefCell::new( String::new() )); // 3 cards to receive
+    let my_recvs    = Rc::new( Cell::new( NO_CARD )); // 3 cards to receive
     let my_discard  = MyDiscard::new( deck.clone() ); // for cancellable and multiple selection
 
     let kb_input    = KbInput::new();
-    let my_hand     = Rc::new( Cell::new( NO_CARD ));
-    let the_suits   = Rc::new( Cell::new( NO_CARD ));
 
     let hint_cards  = Rc::new( Cell::new( NO_CARD ));
 
-    let animations = Animations::new( the_suits.clone(), under_the_gun.clone() );
+    let animations = Animations::new();
 
     let welcome_lb = root.add_label( "welcome-lb" )?;
 
+    let score_lbs = vec![
+        root.add_label( "my-score-lb"       -text("0/0") )?,
+        root.add_label( "left-score-lb"     -text("0/0") )?,
+        root.add_label( "opposite-score-lb" -text("0/0") )?,
+        root.add_label( "right-score-lb"    -text("0/0") )?,
+    ];
+
     let start_btn = root.add_button( "start-btn" -text("Start") )?;
-    start_btn.configure( -command( tclosure!( tk, bind:(game_io,deck,the_state), || {
+    start_btn.configure( -command( tclosure!( tk, bind:(deck,game,score_lbs,the_state), || {
         the_state.set( State::Dealing );
-        game_io.send_line( "" );
+        game.borrow_mut().apply( Action::NewGame ).ok();
+        score_lbs.iter().try_for_each( |score_lb| score_lb.configure( -text("0/0") ))?;
         SCORING_CARDS.iter().try_for_each( |card| {
             deck[ card ].place_forget()?;
             Ok(())
@@ -460,64 +457,129 @@ fn main() -> TkResult<()> {
         start_btn.place_forget()
     })))?;
 
-    let score_lbs = vec![
-        root.add_label( "my-score-lb"       -text("0/0") )?,
-        root.add_label( "left-score-lb"     -text("0/0") )?,
-        root.add_label( "opposite-score-lb" -text("0/0") )?,
-        root.add_label( "right-score-lb"    -text("0/0") )?,
-    ];
-
     let pass_btn = root.add_button( "pass-btn" )?;
     let my_passes = MyPasses::new( pass_btn, deck.clone() ); // 3 cards to pass
-    pass_btn.configure( -command( tclosure!( tk, bind:(game_io,the_state,my_passes), || {
-        the_state.set( State::Passing );
-        game_io.send_line( &my_passes.to_string() );
+
+    let show_events = bind!((animations,card_back_btns,deck,game,games_left,hint_cards,my_passes,my_recvs,quit_msg,score_lbs,the_state)
+        move |events: Vec<Event>| -> InterpResult<()> {
+            let mut received = NO_CARD;
+            for event in events {
+                match event {
+                    Event::Dealt{ deal:_, hands } => {
+                        hands[ ME ].iter().enumerate().try_for_each( |(nth, card)| {
+                            deck[ &*card.text( FOR_TK )].place( -relx(0.34+0.02*(nth as f64)) -rely(0.80) )?;
+                            InterpResult::Ok(())
+                        })?;
+
+                        let game = game.borrow();
+                        if game.needs_pass_cards() {
+                            place_pass_btn( pass_btn, &game )?;
+                            hint_cards.set( game.pick_cards_for( ME ).dest );
+                            the_state.set( State::WaitForPassing );
+                        } else {
+                            the_state.set( State::Discarding );
+                        }
+                    }
+                    Event::Received{ who: ME, from:_, cards } => {
+                        received += cards;
+                        my_passes.forget()?;
+                        game.borrow().player( ME ).hand.iter().enumerate().try_for_each( |(nth, card)| {
+                            let x = 0.34+0.02*(nth as f64);
+                            let y = if received.contains( card ) {0.77} else {0.80};
+                            deck[ &*card.text( FOR_TK )].place( -relx(x) -rely(y) )?.configure( -state("disabled") )?;
+                            InterpResult::Ok(())
+                        })?;
+                        my_recvs.set( received );
+                        the_state.set( State::ToAccept );
+                    }
+                    Event::Discarded{ who, card } if who != ME => {
+                        let cards_left = game.borrow().player( who ).hand.count() as usize;
+                        let to_discard = card_back_btns[ 13*(who-1) + DISCARD_ORDER[ 12-cards_left ]];
+                        animations.discard( who, &card.text( FOR_TK ), to_discard, tk )?;
+                    }
+                    Event::TrickWon{ trick, points:_ } => {
+                        update_score_lbs( &score_lbs, &game.borrow() )?;
+                        (0..4).for_each( |nth| {
+                            let card = trick.cards[ (trick.leader+nth) % 4 ];
+                            animations.hide( &card.text( FOR_TK ), nth != 3 );
+                        });
+                    }
+                    Event::GameOver{ winner:_ } => {
+                        the_state.set( State::CheckWinner );
+                        if let Some( count ) = games_left.get() {
+                            let count = count.saturating_sub( 1 );
+                            games_left.set( Some( count ));
+                            if count == 0 {
+                                let game = game.borrow();
+                                let p = |who| game.player( who );
+                                *quit_msg.borrow_mut() = Some( format!(
+                                    "YOU won {}, LEFT won {}, OPPOSITE won {}, RIGHT won {}.",
+                                    p(ME).awards, p(LEFT).awards, p(OPPOSITE).awards, p(RIGHT).awards ));
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Ok(())
+        }
+    );
+
+    pass_btn.configure( -command( tclosure!( tk, bind:(game,my_passes,show_events), || {
         pass_btn.configure( -state("disabled") )?;
-        pass_btn.place_forget()
+        pass_btn.place_forget()?;
+
+        let cards = my_passes.to_string().parse().unwrap_or( NO_CARD );
+        let mut events = game.borrow_mut().apply_undoable( Action::Pass{ who: ME, cards }).unwrap_or_default();
+        for who in OTHERS {
+            let cards = game.borrow().pick_cards_for( who ).dest;
+            events.extend( game.borrow_mut().apply( Action::Pass{ who, cards }).unwrap_or_default() );
+        }
+        show_events( events )
     })))?;
 
     let acpt_btn = root.add_button( "acpt-btn" -text("Accept") )?;
-    acpt_btn.configure( -command( tclosure!( tk, bind:(deck,my_recvs,round_cnt,the_state,the_suits), || {
-        the_state.set( State::Obey );
-        the_suits.set( CLUBS );
-        round_cnt.set( 1 );
-        deck.iter().try_for_each( |(name, btn)| {
-            if my_recvs.borrow().contains( name ) {
-                btn.place( -rely(0.80) )?;
-            }
-            btn.configure( -state("normal") )
+    acpt_btn.configure( -command( tclosure!( tk, bind:(deck,my_recvs,the_state), || {
+        the_state.set( State::Discarding );
+        my_recvs.replace( NO_CARD ).iter().try_for_each( |card| {
+            deck[ &*card.text( FOR_TK )].place( -rely(0.80) )?;
+            InterpResult::Ok(())
         })?;
-        my_recvs.borrow_mut().clear();
+        deck.values().try_for_each( |btn| btn.configure( -state("normal") ))?;
         acpt_btn.place_forget()
     })))?;
 
     deck.iter().try_for_each( bind!((quit_msg) |(card, btn)| {
         btn.configure( -command(
-            tclosure!( tk, bind:(animations,btn,card,deck,my_discard,my_hand,my_passes,round_cnt,the_state), || {
+            tclosure!( tk, bind:(animations,btn,card,deck,game,my_discard,my_passes,the_state), || {
                 if animations.is_empty() {
-                    if the_state.get() == State::ShowWinner {
-                        if quit_msg.borrow().is_some() {
+                    match the_state.get() {
+                        State::ShowWinner => if quit_msg.borrow().is_some() {
                             the_state.set( State::Exit );
                         } else {
                             restart_game( &deck, start_btn )?;
+                        },
+                        State::WaitForPassing if pass_btn.winfo_viewable()?
+                            && !my_passes.remove( card )?
+                            && my_passes.len() < 3 =>
+                        {
+                            my_passes.insert( card.to_owned() )?;
                         }
-                    } else if round_cnt.get() == 0 { // passing cards
-                        if pass_btn.winfo_viewable()? {
-                            if !my_passes.remove( card )? {
-                                if my_passes.len() < 3 {
-                                    my_passes.insert( card.to_owned() )?;
+                        State::Discarding | State::WaitForDiscarding => {
+                            let game = game.borrow();
+                            let card_to_discard = card.parse().unwrap_or( NO_CARD );
+                            if my_discard.is( card ) {
+                                if the_state.get() == State::WaitForDiscarding {
+                                    match game.break_the_rule( ME, card_to_discard ) {
+                                        Some( rule ) => game.hint_break_the_rule( card_to_discard, rule ),
+                                        None => animations.discard( ME, card, btn, tk )?,
+                                    }
                                 }
+                            } else if game.player( ME ).hand.contains( card_to_discard ) {
+                                my_discard.select( card )?;
                             }
-                        } else {
-                            the_state.set( State::Welcome );
-                            deck.values().try_for_each( |btn| btn.place_forget() )?;
-                        }
-                    } else { // discarding card
-                        if my_discard.is( card ) {
-                            animations.discard( ME, card, btn, tk )?;
-                        } else if my_hand.get().contains( card.parse().unwrap_or( NO_CARD )) {
-                            my_discard.select( card )?;
                         }
+                        _ => (),
                     }
                 }
                 Ok(())
@@ -531,7 +593,7 @@ fn main() -> TkResult<()> {
         queue
             .iter_mut()
             .take_while( |animation| (*animation).in_parallel().and_extra(one) )
-            .try_for_each( |animation| Ok( match animation {
+            .try_for_each( |animation| { match animation {
                 Animation::Move( mov ) => {
                     let (x, y);
                     if mov.percent < 100 {
@@ -549,7 +611,7 @@ fn main() -> TkResult<()> {
                         hide.percent += if automatic {100} else {20};
                     }
                 }
-            }))?;
+            } InterpResult::Ok(()) })?;
         while queue.front().map( |front| front.is_completed() ).unwrap_or( false ) {
             match queue.pop_front().unwrap() {
                 Animation::Move( mov ) => if let Some( done_state ) = mov.done_state {
@@ -586,7 +648,7 @@ fn main() -> TkResult<()> {
     let kb_input_lb = root.add_label( "kb-input" )?;
 
     root.bind( event::any_key_press(), tclosure!( tk,
-        bind:(animations,deck,my_discard,my_hand,my_passes,quit_msg,round_cnt,round_winner,the_state,the_suits),
+        bind:(animations,deck,game,my_discard,my_passes,quit_msg,the_state),
         |evt_key| -> TkResult<()> {
             if the_state.get() == State::ShowWinner {
                 if quit_msg.borrow().is_some() {
@@ -610,13 +672,8 @@ fn main() -> TkResult<()> {
                     }
 
                     let input = kb_input.to_string();
-                    let hand = my_hand.get();
-
-                    let suit = if round_winner.get() == ME && round_cnt.get() != 1 {
-                        NO_CARD
-                    } else {
-                        the_suits.get()
-                    };
+                    let hand = game.borrow().player( ME ).hand;
+                    let suit = game.borrow().suit_mask;
 
                     if submit {
                         if start_btn.winfo_viewable()? {
@@ -638,7 +695,7 @@ fn main() -> TkResult<()> {
                         kb_input.clear();
                     } else {
                         hand.text( FOR_TK ).split(' ').try_for_each( |card| unselect_card( &deck, card ))?;
-                        if round_cnt.get() == 0 {
+                        if the_state.get() == State::WaitForPassing {
                             match Cards::parse_in_hand( &input, hand, suit ) {
                                 Ok( cards ) | Err( ParseError::Ambiguous{ cards, ok:_, err:_ }) => {
                                     my_passes.clear()?;
@@ -670,7 +727,7 @@ fn main() -> TkResult<()> {
                                         }
                                     }
                                     Err( ParseError::Ambiguous{ cards, ok:_, err:_ }) => {
-                                        let card = cards.iter().cycle().skip( repeat ).next().unwrap();
+                                        let card = cards.iter().cycle().nth( repeat ).unwrap();
                                         if hand.contains( card ) {
                                             my_discard.select( &card.text( FOR_TK ))?;
                                             if kb_input.trim_start() {
@@ -699,93 +756,131 @@ fn main() -> TkResult<()> {
         })
     )?;
 
-    let get_hint = bind!((hint_cards) move |line: &str| {
-        if let Some( pos ) = line.find( "e.g." ) {
-            hint_cards.set( line[ pos+4.. ].trim().parse().unwrap() );
+    // Redraws the table after the game is loaded.
+    let redraw = bind!((animations,card_back_btns,deck,game,hint_cards,my_discard,my_passes,score_lbs,the_state) move || -> InterpResult<()> {
+        animations.clear();
+        my_passes.forget()?;
+        my_discard.clear();
+        deck.values().try_for_each( |btn| { btn.place_forget()?; btn.configure( -state("normal") )})?;
+        card_back_btns.iter().try_for_each( |btn| btn.place_forget() )?;
+        welcome_lb.place_forget()?;
+        start_btn.place_forget()?;
+        pass_btn.place_forget()?;
+        acpt_btn.place_forget()?;
+
+        let game = game.borrow();
+        if game.deal() == 0 {
+            the_state.set( State::Welcome );
+            return Ok(());
+        }
+
+        for who in OTHERS {
+            place_card_backs( &card_back_btns, who, game.player( who ).hand.count() as usize )?;
+        }
+        place_score_lbs( &score_lbs )?;
+        update_score_lbs( &score_lbs, &game )?;
+
+        let hand = game.player( ME ).hand;
+        let shift = if game.round == 0 { 0 } else { ( 14 - hand.count() as usize ) / 2 };
+        hand.iter().enumerate().try_for_each( |(nth, card)| {
+            deck[ &*card.text( FOR_TK )].place( -relx(0.34+0.02*( ( nth + shift ) as f64 )) -rely(0.80) )?;
+            InterpResult::Ok(())
+        })?;
+
+        match game.phase() {
+            Phase::Dealing => the_state.set( State::Dealing ),
+            Phase::Passing => {
+                place_pass_btn( pass_btn, &game )?;
+                hint_cards.set( game.pick_cards_for( ME ).dest );
+                the_state.set( State::WaitForPassing );
+            }
+            Phase::Discarding => {
+                let trick = game.trick();
+                PLAYERS.filter( |&who| trick.cards[ who ] != NO_CARD ).try_for_each( |who| {
+                    deck[ &*trick.cards[ who ].text( FOR_TK )].place( -relx(DISCARD_X[ who ]) -rely(DISCARD_Y[ who ]) )?;
+                    InterpResult::Ok(())
+                })?;
+                the_state.set( State::Discarding );
+            }
+            Phase::GameOver => the_state.set( State::CheckWinner ),
         }
+        Ok(())
     });
 
-    tk.run( tclosure!( tk, cmd:"poll_received", bind:(game_io,quit_msg,round_winner,the_state), || {
+    tk.option_add( "*tearOff", 0 )?;
+    let menubar = root.add_menu(())?;
+    let menu_game = menubar.add_menu(())?;
+    menubar.add_cascade( -menu(menu_game) -label("Game") )?;
+    menu_game.add_command( -label("Save...") -command( tclosure!( tk, bind:(game), || -> InterpResult<()> {
+        if let Some( path ) = tk.get_save_file(())? {
+            if let Err( err ) = game.borrow().save_to( &path ) {
+                tk.message_box( -type_("ok") -message(&*format!( "Failed to save to {}: {err}", path.display() )) )?;
+            }
+        }
+        Ok(())
+    })))?;
+    menu_game.add_command( -label("Load...") -command( tclosure!( tk, bind:(game,redraw), || -> InterpResult<()> {
+        if let Some( path ) = tk.get_open_file(())? {
+            let loaded = game.borrow_mut().load_from( &path );
+            match loaded {
+                Ok(()) => redraw()?,
+                Err( err ) => {
+                    tk.message_box( -type_("ok") -message(&*format!( "Failed to load {}: {err}", path.display() )) )?;
+                }
+            }
+        }
+        Ok(())
+    })))?;
+    // Takes back my last pass or discard, while waiting for my move.
+    let undo = bind!((animations,game,redraw,the_state) move || -> InterpResult<()> {
+        let waiting = matches!( the_state.get(), State::WaitForPassing | State::ToAccept | State::WaitForDiscarding );
+        if waiting && animations.is_empty() {
+            let undone = game.borrow_mut().undo();
+            if undone {
+                redraw()?;
+            }
+        }
+        Ok(())
+    });
+    if !no_undo {
+        menu_game.add_command( -label("Undo") -accelerator("Ctrl+Z") -command( tclosure!( tk, bind:(undo), || undo() )))?;
+        root.bind( event::control().key_press( TkKey::z ), tclosure!( tk, bind:(undo), || undo() ))?;
+    }
+    menu_game.add_command( -label("Export replay...") -command( tclosure!( tk, bind:(game), || -> InterpResult<()> {
+        if let Some( path ) = tk.get_save_file(())? {
+            if let Err( err ) = game.borrow().history().write_to( &game.borrow(), &path ) {
+                tk.message_box( -type_("ok") -message(&*format!( "Failed to export to {}: {err}", path.display() )) )?;
+            }
+        }
+        Ok(())
+    })))?;
+    root.configure( -menu(menubar) )?;
+
+    tk.run( tclosure!( tk, cmd:"poll_received", bind:(game,game_io,quit_msg,the_state), || {
+        if let Some( received ) = game_io.try_recv_err() {
+            tk.message_box( -type_("ok") -message(received) )?;
+        }
+
         match the_state.get() {
-            State::Welcome => if let Some( welcome ) = game_io.try_recv_err() {
+            State::Welcome => {
                 SCORING_CARDS.iter().enumerate().try_for_each( |(nth, card)| {
                     deck[ card ].place( -relx(0.15) -rely(0.01+0.05*(nth as f64)) )?;
                     Ok(())
                 })?;
                 welcome_lb.place( -relx(0.27) -rely(0.00) )?
-                    .configure( -text(welcome) -justify("left") )?;
+                    .configure( -text(&*format!( "Welcome to play heartless!\n\n{RULES}{}", game.borrow().house_rules() )) -justify("left") )?;
 
                 start_btn.place( -relx(0.48) -rely(0.75) )?;
-                game_io.recv_line(); // Press enter to start...
                 the_state.set( State::WaitForStart );
             }
             State::Dealing => if animations.is_empty() {
-                for i in 0..13 {
-                    card_back_btns[i+ 0].place( -relx(0.14) -rely(0.20+0.03*(i as f64)) )?; // LEFT
-                    card_back_btns[i+13].place( -relx(0.34+0.02*(i as f64)) -rely(0.01) )?; // OPPOSITE
-                    card_back_btns[i+26].place( -relx(0.79) -rely(0.20+0.03*(i as f64)) )?; // RIGHT
+                for who in OTHERS {
+                    place_card_backs( &card_back_btns, who, 13 )?;
                 }
+                place_score_lbs( &score_lbs )?;
 
-                score_lbs[ ME       ].place( -relx(0.51) -rely(0.51) )?;
-                score_lbs[ LEFT     ].place( -relx(0.47) -rely(0.46) )?;
-                score_lbs[ OPPOSITE ].place( -relx(0.51) -rely(0.41) )?;
-                score_lbs[ RIGHT    ].place( -relx(0.55) -rely(0.46) )?;
-
-                the_state.set( State::Dealed );
-            }
-            State::Dealed => if let Some( hand_line ) = game_io.try_recv_line() {
-                round_cnt.set( 0 );
-
-                if hand_line.starts_with('=') {
-                    my_hand.set( hand_line[1..].trim().parse().unwrap() );
-                    hand_line[1..].trim().split_whitespace().enumerate()
-                        .try_for_each( |(nth, card)| {
-                            deck[ card ].place( -relx(0.34+0.02*(nth as f64)) -rely(0.80) )?;
-                            Ok(())
-                        })?;
-                } else {
-                    panic!("The line \"{hand_line}\" should starts with '='.");
-                }
-
-                let pass_hint_line = game_io.recv_line();
-                if pass_hint_line.starts_with("Pass 3 cards to ") {
-                    pass_btn.place( -relx(0.44) -rely(0.65) )?
-                        .configure( -state("disabled") )?;
-                    if let Some( pos ) = pass_hint_line.find( ',' ) {
-                        pass_btn.configure( -text(&pass_hint_line[..pos]) )?;
-                    }
-                    get_hint( &pass_hint_line );
-                    the_state.set( State::WaitForPassing );
-                } else {
-                    the_suits.set( CLUBS );
-                    round_cnt.set( 1 );
-                    if pass_hint_line.starts_with("Do not need to pass cards. Discard 1 card") {
-                        get_hint( &pass_hint_line );
-                        the_state.set( State::WaitForDiscarding );
-                    } else if pass_hint_line.starts_with("Do not need to pass cards") {
-                        the_state.set( State::Discarding );
-                    }
-                }
-            }
-            State::Passing => if let Some( line ) = game_io.try_recv_line() {
-                if line.starts_with( "Received " ) {
-                    *my_recvs.borrow_mut() = line[9..].trim().to_owned();
-                    the_state.set( State::Received );
-                }
-            }
-            State::Received => if let Some( line ) = game_io.peek_line() {
-                if line.starts_with( '=' ) {
-                    my_passes.forget()?;
-                    line[1..].trim().split_whitespace().enumerate()
-                        .try_for_each( |(nth, card)| {
-                            let x = 0.34+0.02*(nth as f64);
-                            let y = if my_recvs.borrow().contains( card ) {0.77} else {0.80};
-                            deck[ &*card ].place( -relx(x) -rely(y) )?.configure( -state("disabled") )?;
-                            Ok(())
-                        })?;
-                    my_hand.set( line[1..].trim().parse().unwrap() );
-                    the_state.set( State::ToAccept );
-                }
+                let events = game.borrow_mut().deal_cards().unwrap_or_default();
+                show_events( events )?;
             }
             State::ToAccept => {
                 acpt_btn.place( -relx(0.48) -rely(0.65) )?;
@@ -793,158 +888,68 @@ fn main() -> TkResult<()> {
                     acpt_btn.invoke()?;
                 }
             }
-            State::Discarding => {
-                if let Some( received ) = game_io.try_recv_err() {
-                    if received.chars().next() != Some('\n') && received != "Press enter to start...\n" {
-                        tk.message_box( -type_("ok") -message(received) )?;
-                    } // skip ASCII hearts
-                }
-                if let Some( received ) = game_io.try_recv_lines(8) {
-                    let mut lines = received.lines();
-
-                    let mut discarding_cards = ["","","",""];
-                    let mut win = NOBODY;
-                    let mut gun = NOBODY;
-
-                    let splitter = lines.next().unwrap();
-                    let (round_is_pending, round);
-
-                    match splitter.find('.') {
-                        Some( offset ) => {
-                            round_is_pending = false;
-                            round = offset / 2 + 1;
-                        },
-                        None => {
-                            round_is_pending = true;
-                            round = splitter.find("  -").unwrap_or(24) / 2 + 1;
-                        },
-                    }
-                    round_cnt.set( round );
-
-                    if round_is_pending {
-                        for _ in 0..4 { lines.next(); } // skip 3 score lines and 1 empty line
-                    } else {
-                        let opposite_scores = lines.next().unwrap().trim();
-                        let mut left_right_scores = lines.next().unwrap().trim().split_whitespace();
-                        let left_scores = left_right_scores.next().unwrap();
-                        let right_scores = left_right_scores.next().unwrap();
-                        let my_scores = lines.next().unwrap().trim();
-
-                        score_lbs[ ME       ].configure( -text(my_scores)       )?;
-                        score_lbs[ LEFT     ].configure( -text(left_scores)     )?;
-                        score_lbs[ OPPOSITE ].configure( -text(opposite_scores) )?;
-                        score_lbs[ RIGHT    ].configure( -text(right_scores)    )?;
-
-                        lines.next(); // skip the empty line between scores and discarding cards
-                    }
-
-                    set_discarding_cards( &mut lines, &mut discarding_cards, &mut gun, &mut win );
-                    under_the_gun.set( gun );
-                    round_winner.set( win );
-                    if win == ME {
-                        the_suits.set( NO_CARD );
-                    }
-
-                    for i in (gun..gun+4).rev() {
-                        let who = i%4;
-                        if who != ME {
-                            if !discarding_cards[who].is_empty() {
-                                let to_discard = card_back_btns[ 13*(who-1) + DISCARD_ORDER[ round-1 ]];
-                                animations.discard( who, discarding_cards[who], to_discard, tk )?;
-                                break;
+            State::Discarding => if animations.is_empty() {
+                let phase = game.borrow().phase();
+                match phase {
+                    Phase::Dealing => the_state.set( State::Dealing ),
+                    Phase::Discarding => {
+                        let who = game.borrow().next_to_discard();
+                        if who == ME {
+                            let hand = game.borrow().player( ME ).hand;
+                            let shift = ( 14 - hand.count() as usize ) / 2;
+                            hand.iter().enumerate().try_for_each( |(nth, card)| {
+                                let x = 0.34+0.02*( ( nth + shift ) as f64 );
+                                deck[ &*card.text( FOR_TK )].place( -relx(x) -rely(0.80) )?.configure( -state("normal") )?;
+                                InterpResult::Ok(())
+                            })?;
+                            if !my_discard.is_empty() {
+                                my_discard.select( &my_discard.to_string() )?;
                             }
+                            hint_cards.set( game.borrow().pick_cards_for( ME ).dest );
+                            the_state.set( State::WaitForDiscarding );
+                        } else {
+                            let card = game.borrow().pick_cards_for( who ).dest;
+                            let events = game.borrow_mut().apply( Action::Discard{ who, card }).unwrap_or_default();
+                            show_events( events )?;
                         }
                     }
-
-                    if round_is_pending {
-                        the_suits.set( discarding_cards[gun].parse::<Cards>().unwrap().suit_mask() );
-                    } else {
-                        discarding_cards.rotate_left( gun );
-                        discarding_cards.iter().enumerate()
-                            .for_each( |(nth, card)| animations.hide( card, /*None,*/ nth != 3 ));
-                    }
-                    the_state.set( State::Obey );
-                }
-            }
-            State::Obey => if let Some( received ) = game_io.peek_lines(2) {
-                let mut lines = received.lines();
-
-                let hand_line = lines.next().unwrap();
-                if hand_line.starts_with('=') && hand_line != "= " {
-                    my_hand.set( hand_line[1..].trim().parse().unwrap() );
-                }
-
-                let cmd = lines.next().unwrap();
-                let discarding = cmd.starts_with("discarding...");
-
-                if discarding {
-                    let round_cnt = if discarding {round_cnt.get()} else {0};
-                    hand_line[1..].trim().split_whitespace().enumerate().try_for_each( |(nth, card)| {
-                        let x = 0.34+0.02*( ( nth + (round_cnt+1)/2 ) as f64 );
-                        let y = 0.80;
-                        deck[ &*card ].place( -relx(x) -rely(y) )?.configure( -state("normal") )?;
-                        Ok(())
-                    })?;
-
-                    game_io.recv_lines(2);
-                    the_state.set( State::Discarding );
-                } else if cmd.starts_with("Discard 1 card") {
-                    game_io.recv_lines(2);
-                    get_hint( cmd );
-                    the_state.set( State::WaitForDiscarding );
-                } else if cmd.starts_with("Pass 3 cards to ") || cmd.starts_with("Do not need to pass cards") {
-                    the_state.set( State::Dealing );
-                } else if cmd.starts_with("Press enter to start...") {
-                    the_state.set( State::CheckWinner );
-                } else if cmd.starts_with("Statistics:") {
-                    the_state.set( State::CheckWinner );
-                    let pos = cmd.find(':').unwrap();
-                    *quit_msg.borrow_mut() = Some( cmd[pos+1..].to_owned() );
-                } else {
-                    panic!( "Don't know how to obey `{cmd}`" );
+                    _ => (),
                 }
             }
             State::Discarded => {
+                the_state.set( State::Discarding );
                 if !my_discard.is_empty() {
-                    let card = my_discard.to_string();
-                    game_io.send_line( &card );
+                    let card = my_discard.to_string().parse().unwrap_or( NO_CARD );
                     my_discard.clear();
+                    let events = game.borrow_mut().apply_undoable( Action::Discard{ who: ME, card }).unwrap_or_default();
+                    show_events( events )?;
                 }
-                the_state.set( State::Discarding );
             }
-            State::CheckWinner => {
-                let received = game_io.recv_lines(2);
-                let mut lines = received.lines();
-                let first_line = lines.next().unwrap();
-                let _ = lines.next().unwrap();
-                let the_winner_is = "The winner is ";
-                assert!( first_line.starts_with( the_winner_is ));
-
-                let winner = match first_line[ the_winner_is.len().. ].trim_end_matches('!') {
-                    "me"                   => ME,
-                    "the left player"      => LEFT,
-                    "the opposite player"  => OPPOSITE,
-                    "the right player"     => RIGHT,
-                    winner                 => panic!("{winner}"),
-                };
-
-                const MSG: [&'static str; 4] = [
+            State::CheckWinner => if animations.is_empty() {
+                const MSG: [&str; 4] = [
                     "You won the game!",
                     "The left player won the game!",
                     "The opposite player won the game!",
                     "The right player won the game!",
                 ];
 
+                const TEAM_MSG: [&str; 2] = [
+                    "You and your partner won the game!",
+                    "The left and right players won the game!",
+                ];
+
+                let winner = game.borrow().winner();
+                let partnership = game.borrow().partner( winner ) != NOBODY;
                 if !automatic {
-                    tk.message_box( -type_("ok") -message(MSG[winner]) )?;
+                    let msg = if partnership { TEAM_MSG[winner] } else { MSG[winner] };
+                    tk.message_box( -type_("ok") -message(msg) )?;
                 }
                 if winner == ME {
                     the_state.set( State::ShowWinner );
                 } else if quit_msg.borrow().is_some() {
                     the_state.set( State::Exit );
                 } else {
-                    game_io.send_line("");
-                    the_state.set( State::Dealing );
+                    start_btn.invoke()?;
                 }
             }
             State::ShowWinner => {
@@ -978,5 +983,6 @@ fn main() -> TkResult<()> {
         Ok(())
     }))?;
 
-    Ok( main_loop() )
+    main_loop();
+    Ok(())
 }